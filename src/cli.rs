@@ -0,0 +1,333 @@
+use std::str::FromStr;
+
+use rand::Rng;
+
+use crate::config::{ExperimentConfig, TrainingConfig};
+use crate::population::EvaluationMode;
+use crate::snakegame::GameConfig;
+
+pub const DEFAULT_OUT_DIR: &str = "runs/latest";
+
+const PLAY_DEFAULT_GAMES: usize = 1;
+const PLAY_DEFAULT_DELAY_MS: u64 = 100;
+const EVAL_DEFAULT_GAMES: usize = 100;
+const BENCH_DEFAULT_ROUNDS: usize = 5;
+
+pub const USAGE: &str = "\
+Usage: AI_Snake_rust <command> [options]
+
+Commands:
+  train    Evolve a new population
+             --config experiment.json
+             --generations N  --population N  --elites N  --crossovers N  --randoms N
+             --iterations N   --evaluation common|independent  --seed N
+             --checkpoint-every N  --save-best-every N  --out DIR
+  resume   Continue a run from its checkpoint
+             --out DIR  --generations N
+  play     Watch a saved member play in the terminal
+             <members.json>  --member I  --games N  --delay-ms N  --seed N  --config experiment.json
+  eval     Score saved members on seeded games without rendering
+             <members.json>  --member I  --games N  --seed N  --config experiment.json
+  replay   Print every step of one seeded game, for debugging
+             <members.json>  --seed N  --member I  --config experiment.json
+  bench    Measure fitness evaluation throughput
+             --population N  --iterations N  --rounds N
+
+Flags override the values of the experiment file. Without an experiment file the episode seed is
+random; with one it is taken from the file unless --seed is given. play, eval and replay use the
+board of the experiment file, or the default board without one.
+
+train and resume append a row per generation to stats.jsonl and stats.csv in the output directory.
+With \"genome\": { \"Neat\": {} } in the experiment file, train evolves NEAT genomes instead of fixed
+layers; resume picks up neat_checkpoint.json and the best genomes are saved as best_genomes_N.json.
+";
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Train { config: Box<ExperimentConfig>, out_dir: String },
+    Resume { out_dir: String, generations: Option<usize> },
+    Play { members_path: String, member: usize, games: usize, delay_ms: u64, seed: Option<u64>, game: GameConfig },
+    Eval { members_path: String, member: Option<usize>, games: usize, seed: u64, game: GameConfig },
+    Replay { members_path: String, member: usize, seed: u64, game: GameConfig },
+    Bench { population_size: usize, iterations: usize, rounds: usize },
+}
+
+/// Parses the arguments following the binary name
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    let Some((command, rest)) = args.split_first() else {
+        return Err("missing command".to_string());
+    };
+    let mut flags = Flags::parse(rest)?;
+
+    let command = match command.as_str() {
+        "train" => {
+            let mut config: ExperimentConfig = match flags.take::<String>("config")? {
+                Some(path) => ExperimentConfig::load(&path)?,
+                None => {
+                    let mut config = ExperimentConfig::default();
+                    config.training.evaluation = EvaluationMode::CommonSeeds { base_seed: rand::rng().random() };
+                    config
+                }
+            };
+
+            let training: &mut TrainingConfig = &mut config.training;
+            let seed: Option<u64> = flags.take("seed")?;
+            match flags.take::<String>("evaluation")?.as_deref() {
+                None => {}
+                Some("common") if training.evaluation == EvaluationMode::Independent => {
+                    training.evaluation = EvaluationMode::CommonSeeds { base_seed: rand::rng().random() };
+                }
+                Some("common") => {}
+                Some("independent") if seed.is_some() => {
+                    return Err("--seed only applies to common evaluation".to_string());
+                }
+                Some("independent") => training.evaluation = EvaluationMode::Independent,
+                Some(other) => return Err(format!("unknown evaluation mode: {other}")),
+            }
+            if let Some(base_seed) = seed {
+                training.evaluation = EvaluationMode::CommonSeeds { base_seed };
+            }
+
+            override_with(&mut flags, "generations", &mut training.generations)?;
+            override_with(&mut flags, "population", &mut training.population_size)?;
+            override_with(&mut flags, "elites", &mut training.elites)?;
+            override_with(&mut flags, "crossovers", &mut training.crossovers)?;
+            override_with(&mut flags, "randoms", &mut training.randoms)?;
+            override_with(&mut flags, "iterations", &mut training.iterations)?;
+            override_with(&mut flags, "checkpoint-every", &mut training.checkpoint_every)?;
+            override_with(&mut flags, "save-best-every", &mut training.save_best_every)?;
+            config.validate()?;
+            Command::Train {
+                config: Box::new(config),
+                out_dir: flags.take("out")?.unwrap_or_else(|| DEFAULT_OUT_DIR.to_string()),
+            }
+        }
+        "resume" => Command::Resume {
+            out_dir: flags.take("out")?.unwrap_or_else(|| DEFAULT_OUT_DIR.to_string()),
+            generations: flags.take("generations")?,
+        },
+        "play" => Command::Play {
+            members_path: flags.positional("members.json")?,
+            member: flags.take("member")?.unwrap_or(0),
+            games: flags.take_count("games")?.unwrap_or(PLAY_DEFAULT_GAMES),
+            delay_ms: flags.take("delay-ms")?.unwrap_or(PLAY_DEFAULT_DELAY_MS),
+            seed: flags.take("seed")?,
+            game: game_config(&mut flags)?,
+        },
+        "eval" => Command::Eval {
+            members_path: flags.positional("members.json")?,
+            member: flags.take("member")?,
+            games: flags.take_count("games")?.unwrap_or(EVAL_DEFAULT_GAMES),
+            seed: flags.take("seed")?.unwrap_or(0),
+            game: game_config(&mut flags)?,
+        },
+        "replay" => Command::Replay {
+            members_path: flags.positional("members.json")?,
+            member: flags.take("member")?.unwrap_or(0),
+            seed: flags.take("seed")?.ok_or("replay needs --seed")?,
+            game: game_config(&mut flags)?,
+        },
+        "bench" => Command::Bench {
+            population_size: flags.take_count("population")?.unwrap_or(TrainingConfig::default().population_size),
+            iterations: flags.take_count("iterations")?.unwrap_or(TrainingConfig::default().iterations),
+            rounds: flags.take_count("rounds")?.unwrap_or(BENCH_DEFAULT_ROUNDS),
+        },
+        other => return Err(format!("unknown command: {other}")),
+    };
+
+    flags.finish()?;
+    Ok(command)
+}
+
+fn override_with<T: FromStr>(flags: &mut Flags, name: &str, value: &mut T) -> Result<(), String> {
+    if let Some(v) = flags.take(name)? {
+        *value = v;
+    }
+    Ok(())
+}
+
+/// Board of the experiment given with `--config`, or the default board
+fn game_config(flags: &mut Flags) -> Result<GameConfig, String> {
+    match flags.take::<String>("config")? {
+        Some(path) => {
+            let game = ExperimentConfig::load(&path)?.game;
+            game.validate().map_err(|e| format!("Invalid game in {path}: {e}"))?;
+            Ok(game)
+        }
+        None => Ok(GameConfig::default()),
+    }
+}
+
+/// `--name value` pairs and positional arguments, consumed as the command is built
+struct Flags {
+    named: Vec<(String, String)>,
+    positional: Vec<String>,
+}
+
+impl Flags {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut named = Vec::new();
+        let mut positional = Vec::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if let Some(name) = arg.strip_prefix("--") {
+                let value = iter.next().ok_or(format!("--{name} needs a value"))?;
+                named.push((name.to_string(), value.clone()));
+            } else {
+                positional.push(arg.clone());
+            }
+        }
+        Ok(Flags { named, positional })
+    }
+
+    fn take<T: FromStr>(&mut self, name: &str) -> Result<Option<T>, String> {
+        let Some(idx) = self.named.iter().position(|(n, _)| n == name) else {
+            return Ok(None);
+        };
+        let (_, value) = self.named.remove(idx);
+        value
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("invalid value for --{name}: {value}"))
+    }
+
+    /// Like `take` for counts, which must be at least 1
+    fn take_count(&mut self, name: &str) -> Result<Option<usize>, String> {
+        match self.take(name)? {
+            Some(0) => Err(format!("--{name} must be at least 1")),
+            count => Ok(count),
+        }
+    }
+
+    fn positional(&mut self, what: &str) -> Result<String, String> {
+        if self.positional.is_empty() {
+            return Err(format!("missing argument <{what}>"));
+        }
+        Ok(self.positional.remove(0))
+    }
+
+    /// Errors on anything the command did not consume
+    fn finish(self) -> Result<(), String> {
+        if let Some((name, _)) = self.named.first() {
+            return Err(format!("unknown option --{name}"));
+        }
+        if let Some(arg) = self.positional.first() {
+            return Err(format!("unexpected argument {arg}"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TempPath;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_train_flags_override_defaults() {
+        let command = parse_args(&args(
+            "train --population 20 --elites 4 --crossovers 15 --randoms 1 --seed 7 --out runs/x",
+        ))
+        .unwrap();
+        let Command::Train { config, out_dir } = command else {
+            panic!("expected train, got {command:?}");
+        };
+        assert_eq!(config.training.population_size, 20);
+        assert_eq!(config.training.elites, 4);
+        assert_eq!(config.training.evaluation, EvaluationMode::CommonSeeds { base_seed: 7 });
+        assert_eq!(config.training.generations, TrainingConfig::default().generations);
+        assert_eq!(out_dir, "runs/x");
+    }
+
+    #[test]
+    fn test_train_rejects_inconsistent_population() {
+        let err = parse_args(&args("train --population 50")).unwrap_err();
+        assert!(err.contains("must equal the population size"), "{err}");
+    }
+
+    #[test]
+    fn test_independent_evaluation() {
+        let Command::Train { config, .. } = parse_args(&args("train --evaluation independent")).unwrap() else {
+            panic!("expected train");
+        };
+        assert_eq!(config.training.evaluation, EvaluationMode::Independent);
+        assert!(parse_args(&args("train --evaluation independent --seed 3")).is_err());
+    }
+
+    #[test]
+    fn test_flags_override_experiment_file() {
+        let mut experiment = ExperimentConfig::default();
+        experiment.training.evaluation = EvaluationMode::CommonSeeds { base_seed: 99 };
+        experiment.training.generations = 40;
+        experiment.game.width = 12;
+        let file = TempPath::new("cli.json");
+        let path = file.as_str();
+        experiment.save(path).unwrap();
+
+        let parsed = parse_args(&args(&format!("train --config {path} --generations 60")));
+        let played = parse_args(&args(&format!("play best.json --config {path}")));
+
+        let Command::Train { config, .. } = parsed.unwrap() else {
+            panic!("expected train");
+        };
+        assert_eq!(config.training.generations, 60);
+        assert_eq!(config.training.evaluation, EvaluationMode::CommonSeeds { base_seed: 99 });
+        assert_eq!(config.game.width, 12);
+
+        let Command::Play { game, .. } = played.unwrap() else {
+            panic!("expected play");
+        };
+        assert_eq!(game.width, 12);
+    }
+
+    #[test]
+    fn test_play_positional_and_flags() {
+        let command = parse_args(&args("play best.json --games 3 --delay-ms 0")).unwrap();
+        assert_eq!(
+            command,
+            Command::Play {
+                members_path: "best.json".to_string(),
+                member: 0,
+                games: 3,
+                delay_ms: 0,
+                seed: None,
+                game: GameConfig::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_config_with_invalid_game_is_rejected() {
+        let file = TempPath::new("cli_invalid_game.json");
+        let path = file.as_str();
+        std::fs::write(path, r#"{ "game": { "width": 10, "height": 10, "start": { "x": 40, "y": 5 } } }"#).unwrap();
+
+        for command in ["play best.json", "replay best.json --seed 1", "eval best.json"] {
+            let err = parse_args(&args(&format!("{command} --config {path}"))).unwrap_err();
+            assert!(err.contains("Invalid game") && err.contains("outside"), "{command}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_replay_requires_seed() {
+        assert!(parse_args(&args("replay best.json")).is_err());
+        assert!(parse_args(&args("replay best.json --seed 3")).is_ok());
+    }
+
+    #[test]
+    fn test_unknown_input_is_rejected() {
+        assert!(parse_args(&args("fly")).is_err());
+        assert!(parse_args(&args("resume --bogus 1")).is_err());
+        assert!(parse_args(&args("bench --rounds")).is_err());
+        assert!(parse_args(&args("bench --rounds many")).is_err());
+        assert!(parse_args(&args("bench --rounds 0")).is_err());
+        assert!(parse_args(&args("bench --iterations 0")).is_err());
+        assert!(parse_args(&args("eval best.json --games 0")).is_err());
+        assert!(parse_args(&args("play best.json --games 0")).is_err());
+        assert!(parse_args(&[]).is_err());
+    }
+}
@@ -0,0 +1,329 @@
+use serde::{Deserialize, Serialize};
+
+use crate::environment::Environment;
+use crate::neat::GenomeMode;
+use crate::nn_architecture::{NN_Architecture, Shape};
+use crate::population::{EvaluationMode, GeneticConfig};
+use crate::observation::{ObservationEncoder, ObservationMode, TensorEncoder};
+use crate::snakegame::{GameConfig, Snakegame};
+
+pub const DEFAULT_GENERATIONS: usize = 3000;
+pub const DEFAULT_ITERATIONS: usize = 10; // Games played by each member per generation
+
+pub const DEFAULT_POPULATION_SIZE: usize = 100;
+pub const DEFAULT_ELITES: usize = 10; // Number of best members to keep for the next generation
+pub const DEFAULT_CROSSOVERS: usize = 89; // Number of crossovers to perform
+pub const DEFAULT_RANDOMS: usize = 1; // Number of random members to add
+
+pub const DEFAULT_CHECKPOINT_EVERY: usize = 50; // Generations between checkpoints
+pub const DEFAULT_SAVE_BEST_EVERY: usize = 500; // Generations between saves of the best member
+
+/// Knobs of a training run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrainingConfig {
+    pub generations: usize,
+    pub population_size: usize,
+    pub elites: usize,
+    pub crossovers: usize,
+    pub randoms: usize,
+    pub iterations: usize,
+    pub evaluation: EvaluationMode,
+    /// 0 disables checkpoints
+    pub checkpoint_every: usize,
+    /// 0 disables saving the best member
+    pub save_best_every: usize,
+}
+
+impl Default for TrainingConfig {
+    fn default() -> Self {
+        TrainingConfig {
+            generations: DEFAULT_GENERATIONS,
+            population_size: DEFAULT_POPULATION_SIZE,
+            elites: DEFAULT_ELITES,
+            crossovers: DEFAULT_CROSSOVERS,
+            randoms: DEFAULT_RANDOMS,
+            iterations: DEFAULT_ITERATIONS,
+            evaluation: EvaluationMode::CommonSeeds { base_seed: 0 },
+            checkpoint_every: DEFAULT_CHECKPOINT_EVERY,
+            save_best_every: DEFAULT_SAVE_BEST_EVERY,
+        }
+    }
+}
+
+impl TrainingConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        self.validate_counts()?;
+        self.validate_split()
+    }
+
+    /// Checks the settings every genome mode uses
+    fn validate_counts(&self) -> Result<(), String> {
+        if self.generations == 0 {
+            return Err("generations must be at least 1".to_string());
+        }
+        if self.iterations == 0 {
+            return Err("iterations must be at least 1".to_string());
+        }
+        if self.population_size == 0 {
+            return Err("population size must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// Checks the split into elites, crossovers and randoms, which NEAT does not use
+    fn validate_split(&self) -> Result<(), String> {
+        if self.elites == 0 {
+            return Err("elites must be at least 1, crossovers are bred from them".to_string());
+        }
+        let total = self.elites + self.crossovers + self.randoms;
+        if total != self.population_size {
+            return Err(format!(
+                "elites ({}) + crossovers ({}) + randoms ({}) = {} must equal the population size ({})",
+                self.elites, self.crossovers, self.randoms, total, self.population_size
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Everything that defines an experiment, loaded from a JSON file and written next to the outputs
+/// of a run so every result can be traced back to its settings. Missing fields take their defaults,
+/// and the architecture is sized from the observation and action modes of the game.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExperimentConfig {
+    pub training: TrainingConfig,
+    pub game: GameConfig,
+    pub architecture: NN_Architecture,
+    pub genetic: GeneticConfig,
+    /// Fixed layers or NEAT. NEAT only uses the population size of the training split.
+    pub genome: GenomeMode,
+}
+
+impl ExperimentConfig {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| format!("Could not read {path}: {e}"))?;
+        let mut config: ExperimentConfig =
+            serde_json::from_str(&json).map_err(|e| format!("Invalid experiment config {path}: {e}"))?;
+        let input_shape: Shape = match config.game.observation {
+            ObservationMode::Tensor(options) => {
+                let (channels, rows, cols) = TensorEncoder { options }.shape(&config.game);
+                Shape::Grid { channels, rows, cols }
+            }
+            observation => Shape::Flat(observation.size(&config.game)),
+        };
+        config.architecture.set_input_shape(input_shape);
+        config.architecture.set_output_size(config.game.action_mode.size());
+        config.architecture.infer_shapes().map_err(|e| format!("Invalid architecture in {path}: {e}"))?;
+        Ok(config)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).unwrap();
+        std::fs::write(path, json).map_err(|e| format!("Could not write {path}: {e}"))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.genome {
+            GenomeMode::Layers => self.training.validate()?,
+            GenomeMode::Neat(neat) => {
+                self.training.validate_counts()?;
+                neat.validate()?;
+            }
+        }
+        self.game.validate()?;
+        self.genetic.validate()?;
+        self.architecture.validate()?;
+        check_fits_game(self.architecture.input_size(), self.architecture.output_size(), &self.game)
+    }
+}
+
+/// Checks that `game` is valid and that a network with `inputs` and `outputs` fits its observation
+/// and action spaces
+pub fn check_fits_game(inputs: usize, outputs: usize, game: &GameConfig) -> Result<(), String> {
+    game.validate()?;
+    let env = Snakegame::with_seed(*game, 0);
+    let observation_size: usize = env.observation_space().shape.iter().product();
+    let actions: usize = env.action_space().size();
+    if inputs != observation_size {
+        return Err(format!("Architecture takes {inputs} inputs but the game provides {observation_size}"));
+    }
+    if outputs != actions {
+        return Err(format!("Architecture has {outputs} outputs but the game has {actions} actions"));
+    }
+    Ok(())
+}
+
+/// Path in the temp dir that tests write to, removed when dropped so a failing assertion does not
+/// leave the file or directory behind
+#[cfg(test)]
+pub struct TempPath(std::path::PathBuf);
+
+#[cfg(test)]
+impl TempPath {
+    /// `name` is prefixed with the process id so runs do not share files
+    pub fn new(name: &str) -> Self {
+        TempPath(std::env::temp_dir().join(format!("ai_snake_{}_{name}", std::process::id())))
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.0
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.to_str().expect("Temp paths are valid UTF-8")
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = if self.0.is_dir() { std::fs::remove_dir_all(&self.0) } else { std::fs::remove_file(&self.0) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutation::MutationConfig;
+    use crate::point::Point;
+    use crate::snakegame::ActionMode;
+
+    /// Loads an experiment from a file holding `json`
+    fn load_json(name: &str, json: &str) -> Result<ExperimentConfig, String> {
+        let file = TempPath::new(name);
+        std::fs::write(file.path(), json).unwrap();
+        ExperimentConfig::load(file.as_str())
+    }
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert!(TrainingConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_generation_split_must_match_population_size() {
+        let config = TrainingConfig {
+            crossovers: 80,
+            ..TrainingConfig::default()
+        };
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("must equal the population size"), "{err}");
+    }
+
+    #[test]
+    fn test_neat_only_needs_the_population_size() {
+        let json = r#"{ "training": { "population_size": 50 }, "genome": { "Neat": {} } }"#;
+        let mut config: ExperimentConfig = serde_json::from_str(json).unwrap();
+        assert!(config.validate().is_ok());
+
+        config.training.generations = 0;
+        assert!(config.validate().is_err());
+        config.training.generations = 10;
+        config.genome = GenomeMode::Layers;
+        assert!(config.validate().unwrap_err().contains("must equal the population size"));
+    }
+
+    #[test]
+    fn test_partial_experiment_file_uses_defaults() {
+        let json = r#"{
+            "training": { "generations": 20, "population_size": 10, "elites": 2, "crossovers": 8, "randoms": 0 },
+            "game": { "width": 10, "height": 30, "start": { "x": 5, "y": 15 }, "scoring": { "points_per_apple": 100 } },
+            "genetic": { "mutation": { "sigma": 0.5 } }
+        }"#;
+        let config: ExperimentConfig = serde_json::from_str(json).unwrap();
+
+        assert_eq!(config.training.generations, 20);
+        assert_eq!(config.training.iterations, DEFAULT_ITERATIONS);
+        assert_eq!(config.game.height, 30);
+        assert_eq!(config.game.initial_length, GameConfig::default().initial_length);
+        assert_eq!(config.game.scoring.points_per_apple, 100);
+        assert_eq!(config.game.scoring.points_per_step, GameConfig::default().scoring.points_per_step);
+        assert_eq!(config.genetic.mutation.sigma, 0.5);
+        assert_eq!(config.genetic.mutation.gene_percentage, MutationConfig::default().gene_percentage);
+        assert_eq!(config.architecture, NN_Architecture::new());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_experiment_rejects_architecture_not_matching_game() {
+        let mut config = ExperimentConfig::default();
+        config.architecture.layers[0].input_dim = 5;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_network_must_fit_the_game_it_plays() {
+        let game = GameConfig::default();
+        assert!(check_fits_game(7, 4, &game).is_ok());
+
+        // A 3-output member trained on relative actions does not silently play absolute ones
+        let err = check_fits_game(7, 3, &game).unwrap_err();
+        assert!(err.contains("3 outputs") && err.contains("4 actions"), "{err}");
+        let relative = GameConfig { action_mode: ActionMode::Relative, ..game };
+        assert!(check_fits_game(7, 3, &relative).is_ok());
+        let grid = GameConfig { observation: ObservationMode::Grid, ..game };
+        assert!(check_fits_game(7, 4, &grid).unwrap_err().contains("7 inputs"));
+
+        // An invalid board is reported instead of panicking when the game is built
+        let outside = GameConfig { start: Point { x: 40, y: 5 }, ..game };
+        assert!(check_fits_game(7, 4, &outside).unwrap_err().contains("outside"));
+    }
+
+    #[test]
+    fn test_output_size_follows_action_mode() {
+        let config = load_json("actions.json", r#"{ "game": { "action_mode": "Relative" } }"#).unwrap();
+        assert_eq!(config.architecture.output_size(), 3);
+        assert!(config.validate().is_ok());
+
+        let mut absolute: ExperimentConfig = config.clone();
+        absolute.game.action_mode = ActionMode::Absolute;
+        assert!(absolute.validate().is_err());
+    }
+
+    #[test]
+    fn test_input_size_follows_observation_mode() {
+        let json = r#"{ "game": { "width": 10, "height": 12, "start": { "x": 5, "y": 6 }, "observation": "Grid" } }"#;
+        let config = load_json("observation.json", json).unwrap();
+        assert_eq!(config.architecture.input_size(), 10 * 12 * 3);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_convolution_shapes_follow_the_grid_tensor() {
+        let config = load_json(
+            "conv.json",
+            r#"{
+                "game": { "observation": { "Tensor": { "window": 9, "rotate": true } } },
+                "architecture": { "layers": [
+                    { "kind": { "Conv2D": { "channels": 8, "kernel": 3, "stride": 2, "padding": 1 } }, "activation": "Relu" },
+                    { "kind": "Flatten", "activation": "Relu" },
+                    { "activation": "Sigmoid" }
+                ] }
+            }"#,
+        )
+        .unwrap();
+        // 9x9 with padding 1 and stride 2 gives 5x5 per filter
+        assert_eq!(config.architecture.layers[1].output_dim, 8 * 5 * 5);
+        assert_eq!(config.architecture.output_size(), 4);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_experiment_roundtrip() {
+        let config = ExperimentConfig::default();
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(serde_json::from_str::<ExperimentConfig>(&json).unwrap(), config);
+    }
+
+    #[test]
+    fn test_elites_required() {
+        let config = TrainingConfig {
+            elites: 0,
+            crossovers: 99,
+            ..TrainingConfig::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
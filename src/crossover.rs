@@ -0,0 +1,675 @@
+use ndarray::{Array2, Axis};
+use rand::Rng;
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+
+use crate::member::Member;
+use crate::nn_architecture::LayerKind;
+
+const MIX_TYPE_ALL_PERCENTAGE: usize = 30;
+const MIX_TYPE_HALF_PERCENTAGE: usize = 60;
+
+const MIX_WEIGHTS_PERCENTAGE: usize = 50;
+const MIX_BIASES_PERCENTAGE: usize = 50;
+
+/// Combines the parameters of two parents into a child
+pub trait Crossover {
+    /// Moves genes of `other` into `child`, which starts as a copy of the first parent
+    fn combine(&self, child: &mut Member, other: &Member, rng: &mut StdRng);
+
+    /// New member of `generation` bred from `mem1` and `mem2`
+    fn cross(&self, mem1: &Member, mem2: &Member, generation: usize, rng: &mut StdRng) -> Member {
+        let mut child = Member::with_parameters(
+            mem1.nn_architecture.clone(),
+            mem1.weights.clone(),
+            mem1.biases.clone(),
+            generation,
+        )
+        .expect("Parents are valid members");
+        self.combine(&mut child, mem2, rng);
+        child
+    }
+}
+
+/// Element-wise copy of genes of the second parent, with the extent and the target rolled from
+/// percentages for every crossover
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Mix {
+    /// Rolls below this use `MixType::All`
+    pub type_all_percentage: usize,
+    /// Rolls below this (and above the previous one) use `MixType::Percentage`, the rest `MixType::Single`
+    pub type_half_percentage: usize,
+    pub weights_percentage: usize,
+    pub biases_percentage: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MixType {
+    All,
+    Percentage,
+    Single,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MixTarget {
+    Weights,
+    Biases,
+    Both,
+    Random,
+}
+
+impl Default for Mix {
+    fn default() -> Self {
+        Mix {
+            type_all_percentage: MIX_TYPE_ALL_PERCENTAGE,
+            type_half_percentage: MIX_TYPE_HALF_PERCENTAGE,
+            weights_percentage: MIX_WEIGHTS_PERCENTAGE,
+            biases_percentage: MIX_BIASES_PERCENTAGE,
+        }
+    }
+}
+
+impl Mix {
+    pub fn validate(&self) -> Result<(), String> {
+        let rates = [
+            ("type_all_percentage", self.type_all_percentage),
+            ("type_half_percentage", self.type_half_percentage),
+            ("weights_percentage", self.weights_percentage),
+            ("biases_percentage", self.biases_percentage),
+        ];
+        if let Some((name, rate)) = rates.iter().find(|(_, rate)| *rate > 100) {
+            return Err(format!("Mix {name} must be a percentage, got {rate}"));
+        }
+        if self.type_all_percentage > self.type_half_percentage {
+            return Err("Mix type_all_percentage cannot exceed type_half_percentage".to_string());
+        }
+        Ok(())
+    }
+
+    /// Copies genes of `other` into `child`: all or about half of them, or a single one, of the
+    /// weights, the biases or both
+    pub fn mix(child: &mut Member, other: &Member, mix_type: MixType, mix_target: MixTarget, rng: &mut StdRng) {
+        // Determinar si se cambian pesos y/o biases
+        let (change_weights, change_biases) = match mix_target {
+            MixTarget::Weights => (true, false),
+            MixTarget::Biases => (false, true),
+            MixTarget::Both => (true, true),
+            MixTarget::Random => (rng.random_bool(0.5), rng.random_bool(0.5)),
+        };
+
+        match mix_type {
+            MixType::All | MixType::Percentage => {
+                let perc: usize = match mix_type {
+                    MixType::All => 100,
+                    MixType::Percentage => 50,
+                    MixType::Single => 50 //not reached
+                };
+
+                if change_weights {
+                    for i in 0..child.weights.len() {
+                        for j in 0..child.weights[i].nrows() {
+                            for k in 0..child.weights[i].ncols() {
+                                if rng.random_range(1..=100) <= perc {
+                                    child.weights[i][[j, k]] = other.weights[i][[j, k]];
+                                }
+                            }
+                        }
+                    }
+                }
+
+                if change_biases {
+                    for i in 0..child.biases.len() {
+                        for j in 0..child.biases[i].nrows() {
+                            for k in 0..child.biases[i].ncols() {
+                                if rng.random_range(1..=100) <= perc {
+                                    child.biases[i][[j, k]] = other.biases[i][[j, k]];
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            MixType::Single => {
+                if change_weights && let Some((i, j, k)) = random_gene(rng, &child.weights) {
+                    child.weights[i][[j, k]] = other.weights[i][[j, k]];
+                }
+
+                if change_biases && let Some((i, j, k)) = random_gene(rng, &child.biases) {
+                    child.biases[i][[j, k]] = other.biases[i][[j, k]];
+                }
+            }
+        }
+    }
+}
+
+impl Crossover for Mix {
+    fn combine(&self, child: &mut Member, other: &Member, rng: &mut StdRng) {
+        let roll: usize = rng.random_range(0..100);
+        let mix_type: MixType = if roll < self.type_all_percentage {
+            MixType::All
+        } else if roll < self.type_half_percentage {
+            MixType::Percentage
+        } else {
+            MixType::Single
+        };
+
+        let rollw: usize = rng.random_range(0..100);
+        let rollb: usize = rng.random_range(0..100);
+        let mix_target: MixTarget = if rollw < self.weights_percentage && rollb < self.biases_percentage {
+            MixTarget::Both
+        } else if rollw < self.weights_percentage {
+            MixTarget::Weights
+        } else if rollb < self.biases_percentage {
+            MixTarget::Biases
+        } else {
+            MixTarget::Random
+        };
+
+        Mix::mix(child, other, mix_type, mix_target, rng);
+    }
+}
+
+/// Every gene comes from either parent with even odds
+pub struct Uniform;
+
+impl Crossover for Uniform {
+    fn combine(&self, child: &mut Member, other: &Member, rng: &mut StdRng) {
+        for (gene, theirs) in genes_mut(child).zip(genes(other)) {
+            if rng.random_bool(0.5) {
+                *gene = theirs;
+            }
+        }
+    }
+}
+
+/// The flattened genome of the second parent from a random cut onwards
+pub struct OnePoint;
+
+impl Crossover for OnePoint {
+    fn combine(&self, child: &mut Member, other: &Member, rng: &mut StdRng) {
+        let cut = rng.random_range(0..=genome_len(child));
+        copy_range(child, other, cut, usize::MAX);
+    }
+}
+
+/// The flattened genome of the second parent between two random cuts
+pub struct TwoPoint;
+
+impl Crossover for TwoPoint {
+    fn combine(&self, child: &mut Member, other: &Member, rng: &mut StdRng) {
+        let len = genome_len(child);
+        let (a, b) = (rng.random_range(0..=len), rng.random_range(0..=len));
+        copy_range(child, other, a.min(b), a.max(b));
+    }
+}
+
+/// Whole layers, weights and biases together, from either parent with even odds
+pub struct LayerSwap;
+
+impl Crossover for LayerSwap {
+    fn combine(&self, child: &mut Member, other: &Member, rng: &mut StdRng) {
+        for i in 0..child.weights.len() {
+            if rng.random_bool(0.5) {
+                child.weights[i].assign(&other.weights[i]);
+                child.biases[i].assign(&other.biases[i]);
+            }
+        }
+    }
+}
+
+/// Whole neurons, a row of weights with its bias, from either parent with even odds
+pub struct NeuronSwap;
+
+impl Crossover for NeuronSwap {
+    fn combine(&self, child: &mut Member, other: &Member, rng: &mut StdRng) {
+        for i in 0..child.weights.len() {
+            for row in 0..child.biases[i].nrows() {
+                if rng.random_bool(0.5) {
+                    child.weights[i].row_mut(row).assign(&other.weights[i].row(row));
+                    child.biases[i].row_mut(row).assign(&other.biases[i].row(row));
+                }
+            }
+        }
+    }
+}
+
+/// Neuron swap after reordering the hidden neurons of the second parent to line up with those of
+/// the first, so the neurons traded play the same role in both networks
+pub struct AlignedNeuronSwap;
+
+impl Crossover for AlignedNeuronSwap {
+    fn combine(&self, child: &mut Member, other: &Member, rng: &mut StdRng) {
+        let aligned = align_neurons(child, other);
+        NeuronSwap.combine(child, &aligned, rng);
+    }
+}
+
+/// BLX-α: every gene drawn uniformly between the two parent values, widened by `alpha` times their
+/// distance on each side
+pub struct Blend {
+    pub alpha: f64,
+}
+
+impl Crossover for Blend {
+    fn combine(&self, child: &mut Member, other: &Member, rng: &mut StdRng) {
+        for (gene, theirs) in genes_mut(child).zip(genes(other)) {
+            let (low, high) = (gene.min(theirs), gene.max(theirs));
+            let spread = self.alpha * (high - low);
+            if high - low > 0.0 {
+                *gene = rng.random_range(low - spread..high + spread);
+            }
+        }
+    }
+}
+
+/// Simulated binary crossover: every gene spread around the mean of the parents as a single point
+/// crossover would spread bit strings, closer to the parents for a larger distribution index `eta`
+pub struct SimulatedBinary {
+    pub eta: f64,
+}
+
+impl Crossover for SimulatedBinary {
+    fn combine(&self, child: &mut Member, other: &Member, rng: &mut StdRng) {
+        for (gene, theirs) in genes_mut(child).zip(genes(other)) {
+            let u: f64 = rng.random_range(0.0..1.0);
+            let beta = if u <= 0.5 {
+                (2.0 * u).powf(1.0 / (self.eta + 1.0))
+            } else {
+                (1.0 / (2.0 * (1.0 - u))).powf(1.0 / (self.eta + 1.0))
+            };
+            // Either of the two children of the pair
+            let beta = if rng.random_bool(0.5) { beta } else { -beta };
+            *gene = 0.5 * ((1.0 + beta) * *gene + (1.0 - beta) * theirs);
+        }
+    }
+}
+
+/// Crossover operator of an experiment
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CrossoverKind {
+    Mix(Mix),
+    Uniform,
+    OnePoint,
+    TwoPoint,
+    LayerSwap,
+    NeuronSwap,
+    AlignedNeuronSwap,
+    Blend { alpha: f64 },
+    SimulatedBinary { eta: f64 },
+}
+
+/// Operator picked for a crossover with probability proportional to `weight`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WeightedCrossover {
+    pub operator: CrossoverKind,
+    pub weight: f64,
+}
+
+impl CrossoverKind {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            CrossoverKind::Mix(mix) => mix.validate(),
+            CrossoverKind::Blend { alpha } if !alpha.is_finite() || alpha < 0.0 => {
+                Err(format!("Blend alpha must be zero or positive, got {alpha}"))
+            }
+            CrossoverKind::SimulatedBinary { eta } if !eta.is_finite() || eta < 0.0 => {
+                Err(format!("Simulated binary eta must be zero or positive, got {eta}"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Crossover for CrossoverKind {
+    fn combine(&self, child: &mut Member, other: &Member, rng: &mut StdRng) {
+        match *self {
+            CrossoverKind::Mix(mix) => mix.combine(child, other, rng),
+            CrossoverKind::Uniform => Uniform.combine(child, other, rng),
+            CrossoverKind::OnePoint => OnePoint.combine(child, other, rng),
+            CrossoverKind::TwoPoint => TwoPoint.combine(child, other, rng),
+            CrossoverKind::LayerSwap => LayerSwap.combine(child, other, rng),
+            CrossoverKind::NeuronSwap => NeuronSwap.combine(child, other, rng),
+            CrossoverKind::AlignedNeuronSwap => AlignedNeuronSwap.combine(child, other, rng),
+            CrossoverKind::Blend { alpha } => Blend { alpha }.combine(child, other, rng),
+            CrossoverKind::SimulatedBinary { eta } => SimulatedBinary { eta }.combine(child, other, rng),
+        }
+    }
+}
+
+pub fn validate_operators(operators: &[WeightedCrossover]) -> Result<(), String> {
+    if operators.is_empty() {
+        return Err("At least one crossover operator is needed".to_string());
+    }
+    for weighted in operators {
+        if !weighted.weight.is_finite() || weighted.weight < 0.0 {
+            return Err(format!("Crossover weights must be zero or positive, got {}", weighted.weight));
+        }
+        weighted.operator.validate()?;
+    }
+    if operators.iter().all(|w| w.weight == 0.0) {
+        return Err("At least one crossover operator needs a positive weight".to_string());
+    }
+    Ok(())
+}
+
+/// Operator for the next crossover, drawn by weight
+pub fn pick_operator(operators: &[WeightedCrossover], rng: &mut StdRng) -> CrossoverKind {
+    let total: f64 = operators.iter().map(|w| w.weight).sum();
+    let mut roll: f64 = rng.random_range(0.0..total);
+    for weighted in operators {
+        if roll < weighted.weight {
+            return weighted.operator;
+        }
+        roll -= weighted.weight;
+    }
+    operators.iter().rfind(|w| w.weight > 0.0).expect("Validated operators").operator
+}
+
+/// Copy of `other` computing the same function, with the neurons of every hidden dense or recurrent
+/// layer reordered to match the most similar neurons of `reference`, earliest layer first. Neurons
+/// feeding a convolution or a flatten layer keep their place.
+pub fn align_neurons(reference: &Member, other: &Member) -> Member {
+    let mut aligned = other.clone();
+    let layers = &other.nn_architecture.layers;
+    for i in 0..layers.len().saturating_sub(1) {
+        let alignable = |kind: LayerKind| matches!(kind, LayerKind::Dense | LayerKind::Recurrent);
+        if !alignable(layers[i].kind) || !alignable(layers[i + 1].kind) {
+            continue;
+        }
+        let order = match_neurons(
+            (&reference.weights[i], &reference.biases[i]),
+            (&aligned.weights[i], &aligned.biases[i]),
+        );
+        permute_neurons(&mut aligned, i, &order);
+    }
+    aligned
+}
+
+/// For every neuron of `reference`, the neuron of `other` taking its place. Pairs are made greedily,
+/// closest incoming weights and bias first.
+fn match_neurons(reference: (&Array2<f64>, &Array2<f64>), other: (&Array2<f64>, &Array2<f64>)) -> Vec<usize> {
+    let units = reference.1.nrows();
+    let distance = |r: usize, o: usize| -> f64 {
+        let weights = &reference.0.row(r) - &other.0.row(o);
+        weights.mapv(|d| d * d).sum() + (reference.1[[r, 0]] - other.1[[o, 0]]).powi(2)
+    };
+    let mut pairs: Vec<(f64, usize, usize)> =
+        (0..units).flat_map(|r| (0..units).map(move |o| (r, o))).map(|(r, o)| (distance(r, o), r, o)).collect();
+    pairs.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut order: Vec<Option<usize>> = vec![None; units];
+    let mut taken = vec![false; units];
+    for (_, r, o) in pairs {
+        if order[r].is_none() && !taken[o] {
+            order[r] = Some(o);
+            taken[o] = true;
+        }
+    }
+    order.into_iter().map(|o| o.expect("Every neuron is matched")).collect()
+}
+
+/// Moves neuron `order[r]` of layer `layer` to position `r`, along with its recurrent inputs and the
+/// weights the next layer gives it, so the network computes the same function
+fn permute_neurons(member: &mut Member, layer: usize, order: &[usize]) {
+    let units = order.len();
+    member.weights[layer] = member.weights[layer].select(Axis(0), order);
+    member.biases[layer] = member.biases[layer].select(Axis(0), order);
+    if member.nn_architecture.layers[layer].kind == LayerKind::Recurrent {
+        let inputs = member.weights[layer].ncols() - units;
+        let columns: Vec<usize> = (0..inputs).chain(order.iter().map(|&o| inputs + o)).collect();
+        member.weights[layer] = member.weights[layer].select(Axis(1), &columns);
+    }
+    // A recurrent next layer also has columns for its own state, after the ones of this layer
+    let next = &member.weights[layer + 1];
+    let columns: Vec<usize> = order.iter().copied().chain(units..next.ncols()).collect();
+    member.weights[layer + 1] = next.select(Axis(1), &columns);
+}
+
+/// Position of a random value among the non-empty matrices of `arrays`, none if all are empty
+/// (a flatten layer has no parameters)
+fn random_gene(rng: &mut impl Rng, arrays: &[Array2<f64>]) -> Option<(usize, usize, usize)> {
+    let non_empty: Vec<usize> = (0..arrays.len()).filter(|&i| !arrays[i].is_empty()).collect();
+    if non_empty.is_empty() {
+        return None;
+    }
+    let i = non_empty[rng.random_range(0..non_empty.len())];
+    Some((i, rng.random_range(0..arrays[i].nrows()), rng.random_range(0..arrays[i].ncols())))
+}
+
+/// Genes of a member in genome order: layer by layer, the weights row by row and then the biases
+fn genes(member: &Member) -> impl Iterator<Item = f64> + '_ {
+    member.weights.iter().zip(&member.biases).flat_map(|(w, b)| w.iter().chain(b.iter())).copied()
+}
+
+fn genes_mut(member: &mut Member) -> impl Iterator<Item = &mut f64> {
+    member.weights.iter_mut().zip(member.biases.iter_mut()).flat_map(|(w, b)| w.iter_mut().chain(b.iter_mut()))
+}
+
+fn genome_len(member: &Member) -> usize {
+    member.weights.iter().chain(&member.biases).map(|a| a.len()).sum()
+}
+
+/// Copies the genes of `other` at positions `from..to` of the genome into `child`
+fn copy_range(child: &mut Member, other: &Member, from: usize, to: usize) {
+    for (idx, (gene, theirs)) in genes_mut(child).zip(genes(other)).enumerate() {
+        if (from..to).contains(&idx) {
+            *gene = theirs;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nn_architecture::NN_Architecture;
+    use rand::SeedableRng;
+
+    fn member(seed: [u8; 32]) -> Member {
+        Member::new(NN_Architecture::new(), Some(seed), 0)
+    }
+
+    fn parents() -> (Member, Member) {
+        (
+            Member::new(NN_Architecture::new(), Some([1; 32]), 0),
+            Member::new(NN_Architecture::new(), Some([2; 32]), 0),
+        )
+    }
+
+    /// For every gene of the child, whether it comes from the second parent
+    fn from_second(child: &Member, mem1: &Member, mem2: &Member) -> Vec<bool> {
+        genes(child)
+            .zip(genes(mem1).zip(genes(mem2)))
+            .map(|(gene, (a, b))| {
+                assert!(gene == a || gene == b, "gene {gene} is from neither parent");
+                gene == b && gene != a
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_uniform_mixes_genes_evenly() {
+        let (mem1, mem2) = parents();
+        let child = Uniform.cross(&mem1, &mem2, 1, &mut StdRng::seed_from_u64(1));
+        let origin = from_second(&child, &mem1, &mem2);
+        let fraction = origin.iter().filter(|&&b| b).count() as f64 / origin.len() as f64;
+        assert!((fraction - 0.5).abs() < 0.05, "{fraction}");
+        assert_eq!(child.generation, 1);
+    }
+
+    #[test]
+    fn test_point_crossovers_copy_contiguous_segments() {
+        let (mem1, mem2) = parents();
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..20 {
+            let origin = from_second(&OnePoint.cross(&mem1, &mem2, 0, &mut rng), &mem1, &mem2);
+            assert!(origin.windows(2).all(|w| w[0] <= w[1]), "one point gives a single switch");
+
+            let origin = from_second(&TwoPoint.cross(&mem1, &mem2, 0, &mut rng), &mem1, &mem2);
+            let switches = origin.windows(2).filter(|w| w[0] != w[1]).count();
+            assert!(switches <= 2);
+        }
+    }
+
+    #[test]
+    fn test_layer_and_neuron_swaps_keep_units_together() {
+        let (mem1, mem2) = parents();
+        let mut rng = StdRng::seed_from_u64(3);
+
+        let child = LayerSwap.cross(&mem1, &mem2, 0, &mut rng);
+        for i in 0..child.weights.len() {
+            let from_first = child.weights[i] == mem1.weights[i] && child.biases[i] == mem1.biases[i];
+            let from_second = child.weights[i] == mem2.weights[i] && child.biases[i] == mem2.biases[i];
+            assert!(from_first || from_second);
+        }
+
+        let child = NeuronSwap.cross(&mem1, &mem2, 0, &mut rng);
+        for i in 0..child.weights.len() {
+            for row in 0..child.biases[i].nrows() {
+                let swapped = child.weights[i].row(row) == mem2.weights[i].row(row);
+                assert_eq!(child.biases[i][[row, 0]] == mem2.biases[i][[row, 0]], swapped);
+            }
+        }
+    }
+
+    #[test]
+    fn test_alignment_undoes_a_permutation_of_hidden_neurons() {
+        use crate::nn_architecture::{Activation, Shape};
+
+        let architecture = NN_Architecture::builder(Shape::Flat(7))
+            .recurrent(6, Activation::Tanh)
+            .dense(5, Activation::Relu)
+            .recurrent(4, Activation::Tanh)
+            .dense(3, Activation::Sigmoid)
+            .build()
+            .unwrap();
+        let original = Member::new(architecture, Some([9; 32]), 0);
+        let mut shuffled = original.clone();
+        permute_neurons(&mut shuffled, 0, &[5, 3, 1, 0, 2, 4]);
+        permute_neurons(&mut shuffled, 2, &[2, 3, 0, 1]);
+        assert_ne!(shuffled.weights, original.weights);
+
+        let aligned = align_neurons(&original, &shuffled);
+        assert_eq!(aligned.weights, original.weights);
+        assert_eq!(aligned.biases, original.biases);
+    }
+
+    #[test]
+    fn test_aligned_parent_plays_the_same_games() {
+        let (mem1, mut mem2) = parents();
+        let mut aligned = align_neurons(&mem1, &mem2);
+        assert_ne!(aligned.weights, mem2.weights);
+
+        let game = crate::snakegame::GameConfig::default();
+        for seed in 0..5 {
+            let score = mem2.play_game_to_update_fitness(&game, seed);
+            assert_eq!(aligned.play_game_to_update_fitness(&game, seed), score);
+        }
+        assert_eq!(aligned.steps_survived, mem2.steps_survived);
+
+        let child = AlignedNeuronSwap.cross(&mem1, &mem2, 0, &mut StdRng::seed_from_u64(6));
+        assert!(child.validate().is_ok());
+    }
+
+    #[test]
+    fn test_blend_and_simulated_binary_stay_near_the_parents() {
+        let (mem1, mem2) = parents();
+        let mut rng = StdRng::seed_from_u64(4);
+
+        let child = Blend { alpha: 0.5 }.cross(&mem1, &mem2, 0, &mut rng);
+        for (gene, (a, b)) in genes(&child).zip(genes(&mem1).zip(genes(&mem2))) {
+            let d = (a - b).abs();
+            assert!(gene >= a.min(b) - 0.5 * d && gene <= a.max(b) + 0.5 * d);
+        }
+
+        // A large eta keeps the children on the parents
+        let child = SimulatedBinary { eta: 1e6 }.cross(&mem1, &mem2, 0, &mut rng);
+        for (gene, (a, b)) in genes(&child).zip(genes(&mem1).zip(genes(&mem2))) {
+            assert!((gene - a).abs() < 1e-3 || (gene - b).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_operators_picked_by_weight() {
+        let operators = [
+            WeightedCrossover { operator: CrossoverKind::Uniform, weight: 3.0 },
+            WeightedCrossover { operator: CrossoverKind::LayerSwap, weight: 1.0 },
+            WeightedCrossover { operator: CrossoverKind::OnePoint, weight: 0.0 },
+        ];
+        let mut rng = StdRng::seed_from_u64(5);
+        let picks: Vec<CrossoverKind> = (0..4000).map(|_| pick_operator(&operators, &mut rng)).collect();
+        let uniform = picks.iter().filter(|&&k| k == CrossoverKind::Uniform).count();
+        assert!((uniform as f64 / 4000.0 - 0.75).abs() < 0.03, "{uniform}");
+        assert!(!picks.contains(&CrossoverKind::OnePoint));
+
+        assert!(validate_operators(&operators).is_ok());
+        assert!(validate_operators(&[]).is_err());
+        assert!(validate_operators(&[WeightedCrossover { operator: CrossoverKind::Mix(Mix::default()), weight: 0.0 }]).is_err());
+        let blend = WeightedCrossover { operator: CrossoverKind::Blend { alpha: -1.0 }, weight: 1.0 };
+        assert!(validate_operators(&[blend]).is_err());
+    }
+
+    #[test]
+    fn test_cross_all_weights() {
+        let mem1 = member([1; 32]);
+        let mem2 = member([2; 32]);
+
+        let mut child = mem1.clone();
+        Mix::mix(&mut child, &mem2, MixType::All, MixTarget::Weights, &mut StdRng::seed_from_u64(0));
+
+        // Should be mostly equal to mem2 in weights, and equal to mem1 in biases
+        assert_ne!(child.weights, mem1.weights);
+        assert_eq!(child.biases, mem1.biases);
+    }
+
+    #[test]
+    fn test_cross_single_biases() {
+        let mem1 = member([3; 32]);
+        let mem2 = member([4; 32]);
+
+        let mut child = mem1.clone();
+        Mix::mix(&mut child, &mem2, MixType::Single, MixTarget::Biases, &mut StdRng::seed_from_u64(0));
+
+        assert_eq!(child.weights, mem1.weights); // weights unchanged
+        assert_ne!(child.biases, mem1.biases); // at least one bias changed
+    }
+
+    #[test]
+    fn test_cross_all_of_both() {
+        let mem1 = member([5; 32]);
+        let mem2 = member([6; 32]);
+
+        let mut child = mem1.clone();
+        Mix::mix(&mut child, &mem2, MixType::All, MixTarget::Both, &mut StdRng::seed_from_u64(0));
+
+        assert_eq!(child.weights, mem2.weights);
+        assert_eq!(child.biases, mem2.biases);
+    }
+
+    #[test]
+    fn test_cross_skips_layers_without_parameters() {
+        use crate::nn_architecture::{Activation, Initialization, LayerConfig, Shape};
+
+        let layer = |kind, output_dim| LayerConfig { kind, input_dim: 0, output_dim, activation: Activation::Relu, init: None };
+        let mut architecture = NN_Architecture {
+            layers: vec![
+                layer(LayerKind::Conv2D { channels: 2, kernel: 3, stride: 1, padding: 0 }, 0),
+                layer(LayerKind::Flatten, 0),
+                layer(LayerKind::Dense, 3),
+            ],
+            input_shape: Some(Shape::Grid { channels: 1, rows: 5, cols: 5 }),
+            init: Initialization::default(),
+        };
+        architecture.infer_shapes().unwrap();
+        let mem1 = Member::new(architecture.clone(), Some([7; 32]), 0);
+        let mem2 = Member::new(architecture, Some([8; 32]), 0);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..50 {
+            let mut child = mem1.clone();
+            Mix::mix(&mut child, &mem2, MixType::Single, MixTarget::Both, &mut rng);
+            assert!(child.weights[1].is_empty());
+        }
+    }
+}
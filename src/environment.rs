@@ -0,0 +1,134 @@
+use ndarray::Array2;
+
+/// What an agent sees after every step, a column of values
+pub type Observation = Array2<f64>;
+
+/// Shape and value range of the observations of an environment
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObservationSpace {
+    pub shape: Vec<usize>,
+    pub low: f64,
+    pub high: f64,
+}
+
+/// Actions an environment accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionSpace {
+    /// Actions are the indices `0..n`
+    Discrete(usize),
+}
+
+impl ActionSpace {
+    pub fn size(&self) -> usize {
+        match self {
+            ActionSpace::Discrete(n) => *n,
+        }
+    }
+}
+
+/// Result of taking one action
+#[derive(Debug, Clone)]
+pub struct Step<I> {
+    pub observation: Observation,
+    pub reward: f64,
+    pub done: bool,
+    pub info: I,
+}
+
+/// Gym-style episodic environment, so any kind of agent can be evaluated the same way
+pub trait Environment {
+    /// Environment specific details returned with every step
+    type Info;
+
+    /// Starts a new episode fully determined by `seed` and returns its first observation
+    fn reset(&mut self, seed: u64) -> Observation;
+
+    /// Applies `action`, which must be inside `action_space`
+    fn step(&mut self, action: usize) -> Step<Self::Info>;
+
+    fn observation_space(&self) -> ObservationSpace;
+
+    fn action_space(&self) -> ActionSpace;
+}
+
+/// Anything that picks actions from observations
+pub trait Agent {
+    fn act(&mut self, observation: &Observation) -> usize;
+
+    /// Forgets whatever was remembered from a previous episode, called after every reset
+    fn reset(&mut self) {}
+}
+
+/// Total reward of a finished episode together with the info of its last step
+#[derive(Debug, Clone)]
+pub struct Episode<I> {
+    pub total_reward: f64,
+    pub info: I,
+}
+
+/// Resets `env` with `seed` and lets `agent` play until the episode is done
+pub fn run_episode<E, A>(env: &mut E, agent: &mut A, seed: u64) -> Episode<E::Info>
+where
+    E: Environment,
+    A: Agent + ?Sized,
+{
+    let mut observation: Observation = env.reset(seed);
+    agent.reset();
+    let mut total_reward: f64 = 0.0;
+    loop {
+        let step = env.step(agent.act(&observation));
+        total_reward += step.reward;
+        if step.done {
+            return Episode { total_reward, info: step.info };
+        }
+        observation = step.observation;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snakegame::{ActionMode, GameConfig, GameOutcome, RelativeDirection, Snakegame};
+
+    fn relative() -> GameConfig {
+        GameConfig { action_mode: ActionMode::Relative, ..GameConfig::default() }
+    }
+
+    /// Scripted agent that always plays the same action
+    struct Constant(usize);
+
+    impl Agent for Constant {
+        fn act(&mut self, _observation: &Observation) -> usize {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_scripted_agent_runs_into_wall() {
+        let mut env = Snakegame::with_seed(relative(), 0);
+        let episode = run_episode(&mut env, &mut Constant(RelativeDirection::Infront as usize), 3);
+
+        // The head starts at y = 9 heading north, survives nine moves and leaves the board on the tenth
+        assert_eq!(episode.info.outcome, Some(GameOutcome::Wall));
+        assert_eq!(episode.info.steps, 9);
+        assert_eq!(episode.total_reward, episode.info.score as f64);
+    }
+
+    #[test]
+    fn test_reset_restarts_the_same_episode() {
+        let mut env = Snakegame::with_seed(GameConfig::default(), 0);
+        let first = env.reset(5);
+        env.step(RelativeDirection::Right as usize);
+        assert_eq!(env.reset(5), first);
+        assert_eq!(env.get_total_steps(), 0);
+    }
+
+    #[test]
+    fn test_spaces_describe_the_game() {
+        let mut env = Snakegame::with_seed(relative(), 0);
+        let observation = env.reset(1);
+        assert_eq!(env.observation_space().shape, observation.shape().to_vec());
+        assert!(observation.iter().all(|v| (env.observation_space().low..=env.observation_space().high).contains(v)));
+        assert_eq!(env.action_space(), ActionSpace::Discrete(RelativeDirection::COUNT));
+    }
+}
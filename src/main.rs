@@ -3,56 +3,337 @@ mod nn_architecture; // Import the nn_architecture module
 mod snakegame;
 mod point;
 mod population;
+mod config;
+mod cli;
+mod stats;
+mod environment;
+mod observation;
+mod neat;
+mod crossover;
+mod mutation;
+mod selection;
 
 use population::{Population};
+use neat::{GenomeMode, NeatPopulation};
 use member::{Member};
+use snakegame::{GameConfig, GameOutcome, Snakegame};
+use config::{ExperimentConfig, TrainingConfig};
+use cli::Command;
+use stats::StatsLog;
 use std::fs::File;
 use std::io::Write;
-use serde_json;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use rand::Rng;
 
-const GENS: usize = 3000;
-const ITER_PER_MEMBER: usize = 10;
-
-const POP_SIZE: usize = 100; // Population size
-const BEST_N_TO_KEEP: usize = 10; // Number of best members to keep for the next generation
-const CROSSOVER_N: usize = 89; // Number of crossovers to perform
-const RANDOM_N_TO_ADD: usize = 1; // Number of random members to add
+const CONFIG_FILE: &str = "config.json";
+const CHECKPOINT_FILE: &str = "checkpoint.json";
+const NEAT_CHECKPOINT_FILE: &str = "neat_checkpoint.json";
 
 fn main() {
-    let mut pop: Population = Population::new(POP_SIZE, Some(ITER_PER_MEMBER), 0);
-    for generation in 1..GENS {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command: Command = match cli::parse_args(&args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("error: {e}\n\n{}", cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let result: Result<(), String> = match command {
+        Command::Train { config, out_dir } => start_training(*config, &out_dir),
+        Command::Resume { out_dir, generations } => resume_training(&out_dir, generations),
+        Command::Play { members_path, member, games, delay_ms, seed, game } => {
+            play(&members_path, member, games, delay_ms, seed, &game)
+        }
+        Command::Eval { members_path, member, games, seed, game } => eval(&members_path, member, games, seed, &game),
+        Command::Replay { members_path, member, seed, game } => replay(&members_path, member, seed, &game),
+        Command::Bench { population_size, iterations, rounds } => {
+            bench(population_size, iterations, rounds);
+            Ok(())
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    }
+}
+
+fn start_training(config: ExperimentConfig, out_dir: &str) -> Result<(), String> {
+    std::fs::create_dir_all(out_dir).map_err(|e| format!("Could not create {out_dir}: {e}"))?;
+    write_resolved_config(&config, out_dir)?;
+
+    if let GenomeMode::Neat(_) = config.genome {
+        train_neat(NeatPopulation::new(config.clone()), &config.training, out_dir);
+        return Ok(());
+    }
+    let pop: Population = Population::new(config.training.population_size, 0, config.clone());
+    train(pop, &config.training, out_dir);
+    Ok(())
+}
+
+fn resume_training(out_dir: &str, generations: Option<usize>) -> Result<(), String> {
+    let neat_checkpoint_path = Path::new(out_dir).join(NEAT_CHECKPOINT_FILE);
+    if neat_checkpoint_path.exists() {
+        return resume_neat_training(out_dir, neat_checkpoint_path.to_str().unwrap(), generations);
+    }
+    let checkpoint_path = Path::new(out_dir).join(CHECKPOINT_FILE);
+    let checkpoint_path = checkpoint_path.to_str().unwrap();
+    let pop = Population::load_checkpoint(checkpoint_path)
+        .map_err(|e| format!("Could not load checkpoint {checkpoint_path}: {e}"))?;
+
+    // The checkpoint carries the settings of the run, only its length can be changed
+    let mut config: ExperimentConfig = pop.config().clone();
+    if let Some(generations) = generations {
+        config.training.generations = generations;
+        write_resolved_config(&config, out_dir)?;
+    }
+    config.validate()?;
+
+    println!("Resuming from {checkpoint_path} at generation {}", pop.generation());
+    train(pop, &config.training, out_dir);
+    Ok(())
+}
+
+fn resume_neat_training(out_dir: &str, checkpoint_path: &str, generations: Option<usize>) -> Result<(), String> {
+    let pop = NeatPopulation::load_checkpoint(checkpoint_path)
+        .map_err(|e| format!("Could not load checkpoint {checkpoint_path}: {e}"))?;
+
+    let mut config: ExperimentConfig = pop.config().clone();
+    if let Some(generations) = generations {
+        config.training.generations = generations;
+        write_resolved_config(&config, out_dir)?;
+    }
+    config.validate()?;
+
+    println!("Resuming from {checkpoint_path} at generation {}", pop.generation());
+    train_neat(pop, &config.training, out_dir);
+    Ok(())
+}
+
+fn write_resolved_config(config: &ExperimentConfig, out_dir: &str) -> Result<(), String> {
+    let config_path = Path::new(out_dir).join(CONFIG_FILE);
+    let config_path = config_path.to_str().unwrap();
+    config.save(config_path)?;
+    println!("Experiment config written to {config_path}");
+    Ok(())
+}
+
+fn train(mut pop: Population, config: &TrainingConfig, out_dir: &str) {
+    let out_dir = Path::new(out_dir);
+    let checkpoint_path = out_dir.join(CHECKPOINT_FILE);
+    let checkpoint_path = checkpoint_path.to_str().unwrap();
+
+    let mut stats_log: Option<StatsLog> = open_stats_log(out_dir, pop.generation());
+
+    // Numbered like the stats log: `generation` is the one being evaluated
+    for generation in pop.generation()..config.generations {
         println!("Generation {generation}");
-        pop.update_fitness();
+        let stats = pop.update_fitness();
+        append_stats(&mut stats_log, &stats);
+        println!("Episode seeds: {:?}", pop.episode_seeds());
 
         // Create new empty population for the next generation
-        let mut new_pop: Population = Population::new(0, Some(ITER_PER_MEMBER), generation);
+        let mut new_pop: Population = pop.next_generation();
 
         // Get best members to the old population
-        let best_members: Vec<Member> = pop.best_members(BEST_N_TO_KEEP);
+        let best_members: Vec<Member> = pop.best_members(config.elites);
 
         // Save the best member's architecture to a file
-        //let members_to_be_saved: Vec<Member> = pop.best_members(1);
-        //if generation%500==0 {
-        //    let formatted_string: String = format!("best_members_{}.json", generation);
-        //    let _ = save_members_to_file(&members_to_be_saved, &formatted_string);
-        //}
+        if config.save_best_every > 0 && generation % config.save_best_every == 0 {
+            let members_to_be_saved: Vec<Member> = pop.best_members(1);
+            let path = out_dir.join(format!("best_members_{}.json", generation));
+            let path = path.to_str().unwrap();
+            if let Err(e) = save_members_to_file(&members_to_be_saved, path) {
+                eprintln!("Could not save {path}: {e}");
+            }
+        }
 
         // Add parents Members
         new_pop.add_members(best_members.clone());
 
         // Add Crossovers Members
-        new_pop.add_crossovers_members(best_members.clone(), CROSSOVER_N, generation);
-        
+        new_pop.add_crossovers_members(best_members.clone(), config.crossovers, new_pop.generation());
+
         // Add Random Members
-        new_pop.add_random_members( RANDOM_N_TO_ADD, generation);
+        new_pop.add_random_members(config.randoms, new_pop.generation());
 
         pop = new_pop; // Update the population to the new one
+
+        if config.checkpoint_every > 0 && generation % config.checkpoint_every == 0 {
+            match pop.save_checkpoint(checkpoint_path) {
+                Ok(()) => println!("Checkpoint saved to {checkpoint_path}"),
+                Err(e) => eprintln!("Could not save checkpoint {checkpoint_path}: {e}"),
+            }
+        }
+    }
+}
+
+/// Same loop as `train` for NEAT genomes, which breed within their species instead of from a fixed
+/// split of elites, crossovers and randoms
+fn train_neat(mut pop: NeatPopulation, config: &TrainingConfig, out_dir: &str) {
+    let out_dir = Path::new(out_dir);
+    let checkpoint_path = out_dir.join(NEAT_CHECKPOINT_FILE);
+    let checkpoint_path = checkpoint_path.to_str().unwrap();
+    let mut stats_log: Option<StatsLog> = open_stats_log(out_dir, pop.generation());
+
+    for generation in pop.generation()..config.generations {
+        println!("Generation {generation}");
+        let stats = pop.update_fitness();
+        append_stats(&mut stats_log, &stats);
+        println!("Episode seeds: {:?}", pop.episode_seeds());
+
+        if config.save_best_every > 0 && generation % config.save_best_every == 0 {
+            let path = out_dir.join(format!("best_genomes_{}.json", generation));
+            let path = path.to_str().unwrap();
+            let json = serde_json::to_string_pretty(&pop.best_genomes(1)).unwrap();
+            if let Err(e) = std::fs::write(path, json) {
+                eprintln!("Could not save {path}: {e}");
+            }
+        }
+
+        pop.evolve();
+
+        if config.checkpoint_every > 0 && generation % config.checkpoint_every == 0 {
+            match pop.save_checkpoint(checkpoint_path) {
+                Ok(()) => println!("Checkpoint saved to {checkpoint_path}"),
+                Err(e) => eprintln!("Could not save checkpoint {checkpoint_path}: {e}"),
+            }
+        }
+    }
+}
+
+fn open_stats_log(out_dir: &Path, from_generation: usize) -> Option<StatsLog> {
+    match StatsLog::open(out_dir, from_generation) {
+        Ok(log) => Some(log),
+        Err(e) => {
+            eprintln!("Could not open the stats log in {}: {e}", out_dir.display());
+            None
+        }
+    }
+}
+
+fn append_stats(stats_log: &mut Option<StatsLog>, stats: &stats::GenerationStats) {
+    if let Some(log) = stats_log.as_mut()
+        && let Err(e) = log.append(stats)
+    {
+        eprintln!("Could not write the stats log: {e}");
+    }
+}
+
+fn play(
+    path: &str,
+    index: usize,
+    games: usize,
+    delay_ms: u64,
+    seed: Option<u64>,
+    game_config: &GameConfig,
+) -> Result<(), String> {
+    let mut member: Member = load_member(path, index, game_config)?;
+    let first_seed: u64 = seed.unwrap_or_else(|| rand::rng().random());
+
+    let mut results: Vec<(u64, Snakegame)> = Vec::with_capacity(games);
+    for game in 0..games as u64 {
+        let seed = first_seed.wrapping_add(game);
+        let sg = member.watch_game(game_config, seed, Duration::from_millis(delay_ms), true);
+        sg.print_board();
+        results.push((seed, sg));
+    }
+
+    for (seed, sg) in &results {
+        print_game_summary(*seed, sg);
+    }
+    Ok(())
+}
+
+fn replay(path: &str, index: usize, seed: u64, game_config: &GameConfig) -> Result<(), String> {
+    let mut member: Member = load_member(path, index, game_config)?;
+    let sg = member.watch_game(game_config, seed, Duration::ZERO, false);
+    sg.print_board();
+    print_game_summary(seed, &sg);
+    Ok(())
+}
+
+fn eval(path: &str, index: Option<usize>, games: usize, seed: u64, game_config: &GameConfig) -> Result<(), String> {
+    let members: Vec<Member> = load_members_from_file(path, game_config)
+        .map_err(|e| format!("Could not load members from {path}: {e}"))?;
+    let selected: Vec<(usize, Member)> = match index {
+        Some(i) => vec![(i, members.get(i).cloned().ok_or(format!("{path} has no member {i}"))?)],
+        None => members.into_iter().enumerate().collect(),
+    };
+
+    // Every member is scored on the same seeds
+    let seeds: Vec<u64> = (0..games as u64).map(|g| seed.wrapping_add(g)).collect();
+    for (i, mut member) in selected {
+        member.iterate_to_update_fitness(&seeds, game_config);
+        println!(
+            "Member {}: fitness {:.1}, apples {}, killed by wall {}, by itself {}, by reversal {}, by hunger {}, won {}, step limit {}",
+            i,
+            member.fitness,
+            member.apples_eaten,
+            member.outcomes.wall,
+            member.outcomes.self_collision,
+            member.outcomes.reversal,
+            member.outcomes.starvation,
+            member.outcomes.won,
+            member.outcomes.step_limit,
+        );
+    }
+    Ok(())
+}
+
+fn bench(population_size: usize, iterations: usize, rounds: usize) {
+    let mut config = ExperimentConfig::default();
+    config.training.iterations = iterations;
+    let mut pop: Population = Population::new(population_size, 0, config);
+    let games = population_size * iterations;
+    let mut total = Duration::ZERO;
+    for round in 1..=rounds {
+        let start = Instant::now();
+        pop.update_fitness();
+        let elapsed = start.elapsed();
+        total += elapsed;
+        println!("Round {round}: {games} games in {:.3}s", elapsed.as_secs_f64());
     }
+    let games_per_sec = (games * rounds) as f64 / total.as_secs_f64();
+    println!("{:.0} games/s over {rounds} rounds", games_per_sec);
 }
 
-fn save_members_to_file(members: &Vec<Member>, path: &str) -> std::io::Result<()> {
+fn print_game_summary(seed: u64, sg: &Snakegame) {
+    println!(
+        "Seed {}: score {}, apples {}, steps {}, {}",
+        seed,
+        sg.get_score(),
+        sg.apples_eaten,
+        sg.get_total_steps(),
+        sg.outcome().map_or("still alive", GameOutcome::description),
+    );
+}
+
+fn load_member(path: &str, index: usize, game_config: &GameConfig) -> Result<Member, String> {
+    let members: Vec<Member> = load_members_from_file(path, game_config)
+        .map_err(|e| format!("Could not load members from {path}: {e}"))?;
+    members.into_iter().nth(index).ok_or(format!("{path} has no member {index}"))
+}
+
+fn save_members_to_file(members: &[Member], path: &str) -> std::io::Result<()> {
     let json = serde_json::to_string_pretty(members).unwrap(); // or to_string() for compact
     let mut file = File::create(path)?;
     file.write_all(json.as_bytes())?;
     Ok(())
 }
+
+/// Members saved in `path`, each checked on its own and against the spaces of the game they will play
+fn load_members_from_file(path: &str, game_config: &GameConfig) -> std::io::Result<Vec<Member>> {
+    let json = std::fs::read_to_string(path)?;
+    let members: Vec<Member> = serde_json::from_str(&json)?;
+    for (idx, member) in members.iter().enumerate() {
+        let architecture = &member.nn_architecture;
+        member
+            .validate()
+            .and_then(|()| config::check_fits_game(architecture.input_size(), architecture.output_size(), game_config))
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("member {idx}: {e}")))?;
+    }
+    Ok(members)
+}
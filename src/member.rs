@@ -1,35 +1,46 @@
-use ndarray::Array2;
-use rand_distr::{Distribution, Normal};
+use ndarray::{Array2, s};
 use rand::{Rng, SeedableRng, rngs::StdRng, rng};
 use std::cmp::Ordering;
+use std::thread;
+use std::time::Duration;
 
-use crate::nn_architecture::{NN_Architecture, Activation}; 
-use crate::snakegame::{Direction, Snakegame};
+use crate::environment::{run_episode, Agent, Environment, Observation};
+use crate::nn_architecture::{NN_Architecture, Activation, LayerKind, Shape}; 
+use crate::snakegame::{GameConfig, OutcomeCounts, Snakegame};
+use crate::stats::Evaluated;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 // Define the struct
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Member {
     pub fitness: f64,
     pub nn_architecture: NN_Architecture,
     pub weights: Vec<Array2<f64>>,
     pub biases: Vec<Array2<f64>>,
     pub generation: usize, 
-    pub killed_by_wall: usize,
-    pub killed_by_myself: usize,
-    pub killed_by_hunger: usize,
-    pub apples_eaten: usize
+    /// How the games of the last evaluation ended
+    #[serde(default)]
+    pub outcomes: OutcomeCounts,
+    pub apples_eaten: usize,
+    /// Steps played over all the games of the last evaluation
+    #[serde(default)]
+    pub steps_survived: usize,
+    /// Own mutation strength under self-adaptive mutation, inherited by its offspring
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sigma: Option<f64>,
+    /// Fitness of the best parent of a crossover until its first evaluation, to tell whether it improved on them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_fitness: Option<f64>,
+    /// Last output of every recurrent layer in the current game, empty until the first step
+    #[serde(skip)]
+    pub hidden_state: Vec<Array2<f64>>,
 }
 
 /// Implement methods
 impl Member {
-    pub fn new(
-        weights: Option<Vec<Array2<f64>>>,
-        biases: Option<Vec<Array2<f64>>>,
-        seed: Option<[u8; 32]>,
-        generation: usize,
-    ) -> Self {
+    /// Member with random weights and biases for `nn_architecture`, reproducible with `seed`
+    pub fn new(nn_architecture: NN_Architecture, seed: Option<[u8; 32]>, generation: usize) -> Self {
         let mut rng: StdRng = match seed {
                 Some(s) => StdRng::from_seed(s),
                 None => {
@@ -39,106 +50,182 @@ impl Member {
                 }
             };
 
-        let normal: Normal<f64> = rand_distr::Normal::new(0.0, 1.0).unwrap();
-        let nn_architecture: NN_Architecture = NN_Architecture::new();
-        // Use provided weights or generate new ones
-        let weights = weights.unwrap_or_else(|| {
-            nn_architecture
-                .layers
-                .iter()
-                .map(|layer| {
-                    Array2::from_shape_fn(
-                        (layer.output_dim, layer.input_dim),
-                        |_| normal.sample(&mut rng),
-                    )
-                })
-                .collect()
-        });
+        let shapes = nn_architecture.parameter_shapes();
+        let inits = nn_architecture.layer_initializations();
+        let weights: Vec<Array2<f64>> = shapes
+            .iter()
+            .zip(&inits)
+            .map(|(&(shape, _), init)| init.weights.sample(shape, shape.1, shape.0, &mut rng))
+            .collect();
 
-        // Same for biases
-        let biases = biases.unwrap_or_else(|| {
-            nn_architecture
-                .layers
-                .iter()
-                .map(|layer| {
-                    Array2::from_shape_fn((layer.output_dim, 1), |_| normal.sample(&mut rng))
-                })
-                .collect()
-        });
+        // Same for biases, sized by the fans of their layer
+        let biases: Vec<Array2<f64>> = shapes
+            .iter()
+            .zip(&inits)
+            .map(|(&(weights, shape), init)| init.biases.sample(shape, weights.1, weights.0, &mut rng))
+            .collect();
+
+        Self::from_parts(nn_architecture, weights, biases, generation)
+    }
 
+    /// Member with the given weights and biases, which must have the shapes `nn_architecture`
+    /// expects
+    pub fn with_parameters(
+        nn_architecture: NN_Architecture,
+        weights: Vec<Array2<f64>>,
+        biases: Vec<Array2<f64>>,
+        generation: usize,
+    ) -> Result<Self, String> {
+        let member = Self::from_parts(nn_architecture, weights, biases, generation);
+        member.validate()?;
+        Ok(member)
+    }
+
+    /// Checks the architecture and that every weight and bias matrix has the shape it expects, so a
+    /// bad member file is reported on load instead of failing in the middle of a game
+    pub fn validate(&self) -> Result<(), String> {
+        self.nn_architecture.validate()?;
+        let shapes = self.nn_architecture.parameter_shapes();
+        if self.weights.len() != shapes.len() || self.biases.len() != shapes.len() {
+            return Err(format!(
+                "Architecture has {} layers but got {} weight and {} bias matrices",
+                shapes.len(),
+                self.weights.len(),
+                self.biases.len()
+            ));
+        }
+        for (idx, (weight_shape, bias_shape)) in shapes.into_iter().enumerate() {
+            if self.weights[idx].dim() != weight_shape {
+                return Err(format!("Layer {} needs {:?} weights, got {:?}", idx, weight_shape, self.weights[idx].dim()));
+            }
+            if self.biases[idx].dim() != bias_shape {
+                return Err(format!("Layer {} needs {:?} biases, got {:?}", idx, bias_shape, self.biases[idx].dim()));
+            }
+        }
+        Ok(())
+    }
+
+    fn from_parts(
+        nn_architecture: NN_Architecture,
+        weights: Vec<Array2<f64>>,
+        biases: Vec<Array2<f64>>,
+        generation: usize,
+    ) -> Self {
         Self {
             fitness: 0.0,
             nn_architecture,
             weights,
             biases,
-            killed_by_hunger: 0,
-            killed_by_myself: 0,
-            killed_by_wall: 0,
+            outcomes: OutcomeCounts::default(),
             apples_eaten: 0,
-            generation: generation
+            steps_survived: 0,
+            sigma: None,
+            parent_fitness: None,
+            hidden_state: Vec::new(),
+            generation
         }
     }
+
+    /// Clears the memory of the recurrent layers, done at the start of every game
+    pub fn reset_hidden_state(&mut self) {
+        self.hidden_state.clear();
+    }
     
-    fn feedforward(&self, mut a: Array2<f64>) -> Array2<f64> {
+    fn feedforward(&mut self, mut a: Array2<f64>) -> Array2<f64> {
+        let shapes: Vec<Shape> = self.nn_architecture.shapes().expect("Architecture was validated");
+        if self.hidden_state.len() != self.nn_architecture.layers.len() {
+            self.hidden_state = self
+                .nn_architecture
+                .layers
+                .iter()
+                .map(|layer| match layer.kind {
+                    LayerKind::Recurrent => Array2::zeros((layer.output_dim, 1)),
+                    _ => Array2::zeros((0, 1)),
+                })
+                .collect();
+        }
         for (idx, layer) in self.nn_architecture.layers.iter().enumerate() {
             let w: &Array2<f64> = &self.weights[idx];
             let b: &Array2<f64> = &self.biases[idx];
-            a = single_layer_forward_propagation(&a, w, b, layer.activation.clone());
+            a = match layer.kind {
+                LayerKind::Dense => single_layer_forward_propagation(&a, w, b, layer.activation.clone()),
+                LayerKind::Conv2D { kernel, stride, padding, .. } => {
+                    let patches: Array2<f64> = image_patches(&a, shapes[idx], kernel, stride, padding);
+                    let z: Array2<f64> = single_layer_forward_propagation(&patches, w, b, layer.activation.clone());
+                    // One row per filter becomes the channel, row, column order of a grid
+                    Array2::from_shape_vec((z.len(), 1), z.iter().copied().collect())
+                        .expect("A vector always fits a column")
+                }
+                LayerKind::Recurrent => {
+                    let input = ndarray::concatenate![ndarray::Axis(0), a, self.hidden_state[idx]];
+                    let h = single_layer_forward_propagation(&input, w, b, layer.activation.clone());
+                    self.hidden_state[idx] = h.clone();
+                    h
+                }
+                LayerKind::Flatten => a,
+            };
         }
         a
     }
 
-    pub fn play_game_to_update_fitness(&mut self) -> usize {
-        let mut sg = Snakegame::new();
+    /// Plays one seeded game, adding it to the stats, and returns its total reward, the final score
+    pub fn play_game_to_update_fitness(&mut self, game_config: &GameConfig, seed: u64) -> f64 {
+        let mut sg = Snakegame::with_seed(*game_config, seed);
+        let episode = run_episode(&mut sg, self, seed);
 
-        while sg.alive {
-            //sg.print_board();
-            //println!();
-            let input: Array2<f64> = sg.get_current_input(); 
-            let next_move: usize = self.next_move_from_input(input);
-            sg.move_snake(Direction::from_usize(next_move));
-        }
+        self.outcomes.record(episode.info.outcome.expect("A finished episode has an outcome"));
+        self.apples_eaten += episode.info.apples_eaten;
+        self.steps_survived += episode.info.steps;
 
-        if sg.killed_by_hunger {
-            self.killed_by_hunger += 1;
-        }
-        else if sg.killed_by_myself {
-            self.killed_by_myself += 1;
-        }
-        else if sg.killed_by_wall {
-            self.killed_by_wall += 1;
-        }
-        self.apples_eaten += sg.apples_eaten; 
+        episode.total_reward
+    }
+
+    /// Plays one seeded game without touching the fitness stats, printing the board and the chosen
+    /// move at every step and waiting `delay` between frames. With `clear_screen` the frames are
+    /// redrawn in place, otherwise they are printed one after the other. Returns the finished game.
+    pub fn watch_game(&mut self, game_config: &GameConfig, seed: u64, delay: Duration, clear_screen: bool) -> Snakegame {
+        let mut sg = Snakegame::with_seed(*game_config, seed);
+        let mut observation: Observation = sg.observe();
+        self.reset_hidden_state();
+
+        loop {
+            if clear_screen {
+                print!("\x1B[2J\x1B[H");
+            }
+            println!("Seed {} | Step {} | Score {} | Apples {}", seed, sg.get_total_steps(), sg.get_score(), sg.apples_eaten);
+            sg.print_board();
+
+            let next_move: usize = self.next_move_from_input(observation);
+            println!("Move: {}", game_config.action_mode.label(next_move));
+            thread::sleep(delay);
 
-        sg.get_score()
+            let step = sg.step(next_move);
+            if step.done {
+                return sg;
+            }
+            observation = step.observation;
+        }
     }
 
-    pub fn iterate_to_update_fitness(&mut self, iterations: usize) {
+    /// Plays one game per seed and sets the fitness to the average score
+    pub fn iterate_to_update_fitness(&mut self, seeds: &[u64], game_config: &GameConfig) {
 
-        self.killed_by_hunger = 0;
-        self.killed_by_myself = 0;
-        self.killed_by_wall = 0;
+        self.outcomes = OutcomeCounts::default();
         self.apples_eaten = 0;
+        self.steps_survived = 0;
         self.fitness = 0.0;
-        let mut max_score = 0;
-        
-        let mut sum: usize = 0;
-
-        for _ in 0..iterations {
-            let score = self.play_game_to_update_fitness(); 
-            sum += score;
-            if score > max_score {
-                max_score = score;
-            }     
+        let mut sum: f64 = 0.0;
+
+        for &seed in seeds {
+            sum += self.play_game_to_update_fitness(game_config, seed);
+        }
+        // No games played leaves the fitness at 0 instead of NaN
+        if !seeds.is_empty() {
+            self.fitness = sum / seeds.len() as f64;
         }
-        self.fitness = sum as f64 / iterations as f64;
-        //printing stats per member
-        //println!("MyGen {}: KxH={}, KxM={}, KxW={}, AE={}, Fit={:.3}", 
-        //    self.generation, self.killed_by_hunger, self.killed_by_myself, self.killed_by_wall, self.apples_eaten, self.fitness)
-        //print!("{}.",max_score)
     }
 
-    fn next_move_from_input(&self, input: Array2<f64>) -> usize {
+    fn next_move_from_input(&mut self, input: Array2<f64>) -> usize {
         let output: Array2<f64> = self.feedforward(input);
         output
             .iter()
@@ -150,6 +237,42 @@ impl Member {
 
 }
 
+impl Evaluated for Member {
+    fn fitness(&self) -> f64 {
+        self.fitness
+    }
+
+    fn generation(&self) -> usize {
+        self.generation
+    }
+
+    fn outcomes(&self) -> &OutcomeCounts {
+        &self.outcomes
+    }
+
+    fn apples_eaten(&self) -> usize {
+        self.apples_eaten
+    }
+
+    fn steps_survived(&self) -> usize {
+        self.steps_survived
+    }
+
+    fn parameters(&self) -> Vec<f64> {
+        self.weights.iter().chain(self.biases.iter()).flat_map(|a| a.iter().copied()).collect()
+    }
+}
+
+impl Agent for Member {
+    fn act(&mut self, observation: &Observation) -> usize {
+        self.next_move_from_input(observation.clone())
+    }
+
+    fn reset(&mut self) {
+        self.reset_hidden_state();
+    }
+}
+
 /// Forward propagation through the neural network
 fn single_layer_forward_propagation(
     a: &Array2<f64>,
@@ -158,12 +281,41 @@ fn single_layer_forward_propagation(
     activation: Activation,
 ) -> Array2<f64> {
     let z: Array2<f64> = w.dot(a) + b;
+    activate(&z, activation)
+}
+
+/// Applies `activation` to every value of `z`, or to every column for softmax
+pub fn activate(z: &Array2<f64>, activation: Activation) -> Array2<f64> {
     match activation {
-        Activation::Relu => relu(&z),
-        Activation::Sigmoid => sigmoid(&z),
+        Activation::Relu => relu(z),
+        Activation::Sigmoid => sigmoid(z),
+        Activation::Tanh => z.mapv(f64::tanh),
+        Activation::LeakyRelu { alpha } => leaky_relu(z, alpha),
+        Activation::Elu { alpha } => elu(z, alpha),
+        Activation::Linear => z.clone(),
+        Activation::Softmax => softmax(z),
     }
 }
 
+/// Lays out every `kernel` x `kernel` window of the flattened grid `a` as a column, so a
+/// convolution becomes a single product with the filters. Padding cells read as zero.
+fn image_patches(a: &Array2<f64>, shape: Shape, kernel: usize, stride: usize, padding: usize) -> Array2<f64> {
+    let Shape::Grid { channels, rows, cols } = shape else {
+        panic!("Convolutions take a grid, got {shape:?}");
+    };
+    let out_rows = (rows + 2 * padding - kernel) / stride + 1;
+    let out_cols = (cols + 2 * padding - kernel) / stride + 1;
+    let mut padded: ndarray::Array3<f64> = ndarray::Array3::zeros((channels, rows + 2 * padding, cols + 2 * padding));
+    let grid = a.view().into_shape_with_order((channels, rows, cols)).expect("Input matches its shape");
+    padded.slice_mut(s![.., padding..padding + rows, padding..padding + cols]).assign(&grid);
+
+    Array2::from_shape_fn((channels * kernel * kernel, out_rows * out_cols), |(p, q)| {
+        let (c, ky, kx) = (p / (kernel * kernel), p / kernel % kernel, p % kernel);
+        let (y, x) = (q / out_cols * stride + ky, q % out_cols * stride + kx);
+        padded[[c, y, x]]
+    })
+}
+
 /// ReLU activation on a 2D array
 fn relu(z: &Array2<f64>) -> Array2<f64> {
     z.mapv(|x| x.max(0.0))
@@ -174,11 +326,34 @@ fn sigmoid(z: &Array2<f64>) -> Array2<f64> {
     z.mapv(|x| 1.0 / (1.0 + (-x).exp()))
 }
 
+/// Leaky ReLU activation on a 2D array
+fn leaky_relu(z: &Array2<f64>, alpha: f64) -> Array2<f64> {
+    z.mapv(|x| if x > 0.0 { x } else { alpha * x })
+}
+
+/// ELU activation on a 2D array
+fn elu(z: &Array2<f64>, alpha: f64) -> Array2<f64> {
+    z.mapv(|x| if x > 0.0 { x } else { alpha * x.exp_m1() })
+}
+
+/// Softmax of every column of a 2D array, shifted by the column maximum so large values do not
+/// overflow
+fn softmax(z: &Array2<f64>) -> Array2<f64> {
+    let mut out: Array2<f64> = z.clone();
+    for mut column in out.columns_mut() {
+        let max = column.fold(f64::NEG_INFINITY, |m, &x| m.max(x));
+        column.mapv_inplace(|x| (x - max).exp());
+        let sum = column.sum();
+        column.mapv_inplace(|x| x / sum);
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use ndarray::array;
-    use crate::nn_architecture::LayerConfig;
+    use crate::nn_architecture::{Initialization, Initializer, LayerConfig};
     
     #[test]
     fn test_sigmoid_values() {
@@ -196,6 +371,57 @@ mod tests {
         assert_eq!(result, expected, "ReLU output does not match expected values");
     }
 
+    #[test]
+    fn test_tanh_values() {
+        let a = array![[0.0], [1.0], [-2.0]];
+        let w = Array2::eye(3);
+        let b = Array2::zeros((3, 1));
+        let result = single_layer_forward_propagation(&a, &w, &b, Activation::Tanh);
+        assert_eq!(result, array![[0.0], [0.7615941559557649], [-0.9640275800758169]]);
+    }
+
+    #[test]
+    fn test_leaky_relu_values() {
+        let input = array![[-2.0, 0.0, 3.0]];
+        assert_eq!(leaky_relu(&input, 0.1), array![[-0.2, 0.0, 3.0]]);
+    }
+
+    #[test]
+    fn test_elu_values() {
+        let input = array![[-1.0, 0.0, 2.0]];
+        let expected = array![[0.5 * (-1.0_f64).exp_m1(), 0.0, 2.0]];
+        assert_eq!(elu(&input, 0.5), expected);
+    }
+
+    #[test]
+    fn test_linear_is_identity() {
+        let a = array![[-3.5], [2.0]];
+        let result = single_layer_forward_propagation(&a, &Array2::eye(2), &Array2::zeros((2, 1)), Activation::Linear);
+        assert_eq!(result, a);
+    }
+
+    #[test]
+    fn test_softmax_values() {
+        let input = array![[1.0, 1000.0], [2.0, 1000.0], [3.0, 1000.0]];
+        let result = softmax(&input);
+
+        // Each column on its own sums to one, even with values that would overflow e^x
+        let total = 1.0_f64.exp() + 2.0_f64.exp() + 3.0_f64.exp();
+        assert!((result[[2, 0]] - 3.0_f64.exp() / total).abs() < 1e-12);
+        for column in result.columns() {
+            assert!((column.sum() - 1.0).abs() < 1e-12);
+        }
+        assert!((result[[0, 1]] - 1.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_parameterised_activations_serialise_by_name() {
+        let json = serde_json::to_string(&vec![Activation::Tanh, Activation::LeakyRelu { alpha: 0.01 }]).unwrap();
+        assert_eq!(json, r#"["Tanh",{"LeakyRelu":{"alpha":0.01}}]"#);
+        let restored: Vec<Activation> = serde_json::from_str(r#"["Softmax","Linear",{"Elu":{"alpha":1.0}}]"#).unwrap();
+        assert_eq!(restored, vec![Activation::Softmax, Activation::Linear, Activation::Elu { alpha: 1.0 }]);
+    }
+
     #[test]
     fn test_single_layer_forward_propagation_relu_positive() {
         let a = array![[1.0], [2.0]]; // shape (2, 1)
@@ -232,14 +458,7 @@ mod tests {
     #[test]
     fn test_feedforward_with_known_weights_and_biases() {
         // Build a minimal architecture manually
-        let mut nn_arch = NN_Architecture::new();
-        nn_arch.layers = vec![
-            LayerConfig {
-                input_dim: 2,
-                output_dim: 1,
-                activation: Activation::Relu,
-            }
-        ];
+        let nn_arch = NN_Architecture::builder(Shape::Flat(2)).dense(1, Activation::Relu).build().unwrap();
 
         // Set weights and biases to deterministic values
         // Input A: shape (2, 1)
@@ -248,10 +467,7 @@ mod tests {
         // z = W·A + b = [1.0 * 2.0 + (-1.0) * 3.0 + 0.5] = [-0.5] → relu = 0.0
         let weights: Vec<Array2<f64>> = vec![ array![[1.0, -1.0]] ]; // shape (1, 2)
         let biases: Vec<Array2<f64>> = vec![ array![[0.5]] ]; // shape (1, 1)
-        let member: Member = Member::new(Some(weights), Some(biases), None, 0);
-        // Force our test architecture into the struct
-        let mut member = member;
-        member.nn_architecture = nn_arch;
+        let mut member: Member = Member::with_parameters(nn_arch, weights, biases, 0).unwrap();
 
         let input: Array2<f64> = array![[2.0], [3.0]]; // shape (2, 1)
         let output: Array2<f64> = member.feedforward(input);
@@ -266,10 +482,14 @@ mod tests {
         // Define a simple architecture with one layer
         let architecture = NN_Architecture {
             layers: vec![LayerConfig {
+                kind: LayerKind::Dense,
                 input_dim: 2,
                 output_dim: 3,
                 activation: Activation::Relu, // assuming you have a no-op activation
+                init: None,
             }],
+            input_shape: None,
+            init: Initialization::default(),
         };
 
         // Manually set weights and biases for predictable output
@@ -277,15 +497,17 @@ mod tests {
         let biases: Vec<Array2<f64>> = vec![array![[0.0], [0.0], [0.0]]];
 
         // Create a Member with known weights and biases
-        let member = Member {
+        let mut member = Member {
             fitness: 0.0,
             nn_architecture: architecture,
             weights,
             biases,
-            killed_by_hunger: 0,
-            killed_by_myself: 0,
-            killed_by_wall: 0,
+            outcomes: OutcomeCounts::default(),
             apples_eaten: 0,
+            steps_survived: 0,
+            sigma: None,
+            parent_fitness: None,
+            hidden_state: Vec::new(),
             generation: 0
         };
 
@@ -301,4 +523,202 @@ mod tests {
         assert_eq!(result, 0);
     }
 
+    #[test]
+    fn test_convolution_with_known_kernel() {
+        // A 1 channel 3x3 grid, one 2x2 filter summing its window, then flatten
+        let architecture = NN_Architecture {
+            layers: vec![
+                LayerConfig {
+                    kind: LayerKind::Conv2D { channels: 1, kernel: 2, stride: 1, padding: 0 },
+                    input_dim: 9,
+                    output_dim: 4,
+                    activation: Activation::Relu,
+                    init: None,
+                },
+                LayerConfig { kind: LayerKind::Flatten, input_dim: 4, output_dim: 4, activation: Activation::Relu, init: None },
+            ],
+            input_shape: Some(Shape::Grid { channels: 1, rows: 3, cols: 3 }),
+            init: Initialization::default(),
+        };
+        let weights: Vec<Array2<f64>> = vec![array![[1.0, 1.0, 1.0, 1.0]], Array2::zeros((0, 0))];
+        let biases: Vec<Array2<f64>> = vec![array![[-10.0]], Array2::zeros((0, 1))];
+        let mut member = Member::with_parameters(architecture, weights, biases, 0).unwrap();
+
+        let input: Array2<f64> = array![[1.0], [2.0], [3.0], [4.0], [5.0], [6.0], [7.0], [8.0], [9.0]];
+        // Windows sum to 12, 16, 24 and 28
+        assert_eq!(member.feedforward(input), array![[2.0], [6.0], [14.0], [18.0]]);
+    }
+
+    #[test]
+    fn test_padding_keeps_the_grid_size() {
+        let architecture = NN_Architecture {
+            layers: vec![LayerConfig {
+                kind: LayerKind::Conv2D { channels: 1, kernel: 3, stride: 1, padding: 1 },
+                input_dim: 4,
+                output_dim: 4,
+                activation: Activation::Relu,
+                init: None,
+            }],
+            input_shape: Some(Shape::Grid { channels: 1, rows: 2, cols: 2 }),
+            init: Initialization::default(),
+        };
+        let weights: Vec<Array2<f64>> = vec![Array2::ones((1, 9))];
+        let biases: Vec<Array2<f64>> = vec![array![[0.0]]];
+        let mut member = Member::with_parameters(architecture, weights, biases, 0).unwrap();
+
+        // Every padded window covers the whole 2x2 grid
+        assert_eq!(member.feedforward(array![[1.0], [2.0], [3.0], [4.0]]), array![[10.0], [10.0], [10.0], [10.0]]);
+    }
+
+    #[test]
+    fn test_recurrent_layer_remembers_previous_steps() {
+        let architecture = NN_Architecture {
+            layers: vec![LayerConfig { kind: LayerKind::Recurrent, input_dim: 1, output_dim: 1, activation: Activation::Relu, init: None }],
+            input_shape: None,
+            init: Initialization::default(),
+        };
+        // h = relu(x + h_prev), a running sum of the inputs
+        let weights: Vec<Array2<f64>> = vec![array![[1.0, 1.0]]];
+        let biases: Vec<Array2<f64>> = vec![array![[0.0]]];
+        let mut member = Member::with_parameters(architecture, weights, biases, 0).unwrap();
+
+        assert_eq!(member.feedforward(array![[1.0]]), array![[1.0]]);
+        assert_eq!(member.feedforward(array![[2.0]]), array![[3.0]]);
+        assert_eq!(member.feedforward(array![[4.0]]), array![[7.0]]);
+        member.reset_hidden_state();
+        assert_eq!(member.feedforward(array![[2.0]]), array![[2.0]]);
+    }
+
+    #[test]
+    fn test_recurrent_member_is_reproducible_across_games() {
+        let config = GameConfig::default();
+        let outputs = config.action_mode.size();
+        let mut architecture = NN_Architecture {
+            layers: vec![
+                LayerConfig { kind: LayerKind::Recurrent, input_dim: 0, output_dim: 8, activation: Activation::Relu, init: None },
+                LayerConfig { kind: LayerKind::Dense, input_dim: 0, output_dim: outputs, activation: Activation::Sigmoid, init: None },
+            ],
+            input_shape: None,
+            init: Initialization::default(),
+        };
+        architecture.set_input_size(7);
+        architecture.infer_shapes().unwrap();
+        assert!(crate::config::check_fits_game(architecture.input_size(), architecture.output_size(), &config).is_ok());
+
+        // Memory of one game must not leak into the next, so replaying a seed scores the same
+        let mut member = Member::new(architecture, Some([9; 32]), 0);
+        let first = member.play_game_to_update_fitness(&config, 4);
+        member.play_game_to_update_fitness(&config, 5);
+        assert_eq!(member.play_game_to_update_fitness(&config, 4), first);
+    }
+
+    #[test]
+    fn test_convolutional_member_plays_on_the_grid_tensor() {
+        use crate::observation::{ObservationMode, TensorEncoder};
+        use crate::snakegame::GridOptions;
+
+        let options = GridOptions { window: Some(7), rotate: true };
+        let config = GameConfig { observation: ObservationMode::Tensor(options), ..GameConfig::default() };
+        let (channels, rows, cols) = TensorEncoder { options }.shape(&config);
+        let layer = |kind| LayerConfig { kind, input_dim: 0, output_dim: 0, activation: Activation::Relu, init: None };
+        let mut architecture = NN_Architecture {
+            layers: vec![
+                layer(LayerKind::Conv2D { channels: 6, kernel: 3, stride: 1, padding: 0 }),
+                layer(LayerKind::Flatten),
+                LayerConfig { output_dim: config.action_mode.size(), ..layer(LayerKind::Dense) },
+            ],
+            input_shape: Some(Shape::Grid { channels, rows, cols }),
+            init: Initialization::default(),
+        };
+        architecture.infer_shapes().unwrap();
+        assert!(architecture.validate().is_ok());
+        assert!(crate::config::check_fits_game(architecture.input_size(), architecture.output_size(), &config).is_ok());
+
+        let mut member = Member::new(architecture, Some([5; 32]), 0);
+        member.iterate_to_update_fitness(&[1, 2], &config);
+        assert_eq!(member.outcomes.total(), 2);
+    }
+
+    #[test]
+    fn test_parameters_must_match_the_architecture() {
+        let nn_arch = NN_Architecture::builder(Shape::Flat(2)).dense(3, Activation::Relu).build().unwrap();
+        let err = Member::with_parameters(nn_arch.clone(), vec![array![[1.0, -1.0]]], vec![array![[0.5]]], 0)
+            .err()
+            .unwrap();
+        assert!(err.contains("needs (3, 2) weights"), "{err}");
+
+        let err = Member::with_parameters(nn_arch.clone(), vec![], vec![], 0).err().unwrap();
+        assert!(err.contains("1 layers"), "{err}");
+
+        let weights: Vec<Array2<f64>> = vec![Array2::zeros((3, 2))];
+        let err = Member::with_parameters(nn_arch.clone(), weights.clone(), vec![Array2::zeros((2, 1))], 0)
+            .err()
+            .unwrap();
+        assert!(err.contains("biases"), "{err}");
+        assert!(Member::with_parameters(nn_arch, weights, vec![Array2::zeros((3, 1))], 0).is_ok());
+    }
+
+    #[test]
+    fn test_initializers_are_reproducible_from_the_seed() {
+        let architecture = NN_Architecture::builder(Shape::Flat(7))
+            .init(Initializer::He, Initializer::Zeros)
+            .dense(64, Activation::Relu)
+            .dense(3, Activation::Sigmoid)
+            .layer_init(Initializer::XavierUniform, Initializer::Uniform { low: -0.1, high: 0.1 })
+            .build()
+            .unwrap();
+        let m1 = Member::new(architecture.clone(), Some([4; 32]), 0);
+        let m2 = Member::new(architecture, Some([4; 32]), 0);
+
+        assert_eq!(m1.weights, m2.weights);
+        assert_eq!(m1.biases, m2.biases);
+        assert!(m1.biases[0].iter().all(|&b| b == 0.0));
+        assert!(m1.biases[1].iter().all(|b| b.abs() <= 0.1));
+        assert!(m1.weights[1].iter().all(|w| w.abs() <= (6.0_f64 / 67.0).sqrt()));
+        assert_eq!(Initialization::default().weights, Initializer::StandardNormal);
+    }
+
+    #[test]
+    fn test_watched_game_is_the_scored_game() {
+        let config = GameConfig::default();
+        let mut member = Member::new(NN_Architecture::new(), Some([6; 32]), 0);
+        let score = member.play_game_to_update_fitness(&config, 8);
+        let steps = member.steps_survived;
+
+        let watched = member.watch_game(&config, 8, Duration::ZERO, false);
+        assert_eq!(watched.get_score() as f64, score);
+        assert_eq!(watched.get_total_steps(), steps);
+    }
+
+    #[test]
+    fn test_member_json_roundtrip() {
+        let member = Member::new(NN_Architecture::new(), Some([3; 32]), 12);
+        let json = serde_json::to_string(&member).unwrap();
+        let restored: Member = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.weights, member.weights);
+        assert_eq!(restored.biases, member.biases);
+        assert_eq!(restored.generation, 12);
+        assert_eq!(restored.nn_architecture.layers.len(), member.nn_architecture.layers.len());
+    }
+
+    #[test]
+    fn test_fitness_is_reproducible_for_same_seeds() {
+        let config = GameConfig::default();
+        let seeds: Vec<u64> = vec![1, 2, 3, 4, 5];
+
+        let mut m1 = Member::new(NN_Architecture::new(), Some([7; 32]), 0);
+        let mut m2 = m1.clone();
+        m1.iterate_to_update_fitness(&seeds, &config);
+        m2.iterate_to_update_fitness(&seeds, &config);
+
+        assert_eq!(m1.fitness, m2.fitness);
+        assert_eq!(m1.apples_eaten, m2.apples_eaten);
+        assert_eq!(m1.outcomes, m2.outcomes);
+        assert_eq!(m1.outcomes.total(), seeds.len());
+
+        m1.iterate_to_update_fitness(&[], &config);
+        assert_eq!(m1.fitness, 0.0);
+    }
+
 }
@@ -0,0 +1,389 @@
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+use serde::{Deserialize, Serialize};
+
+use crate::member::Member;
+
+const GENE_PERCENTAGE: f64 = 2.0;
+const SIGMA: f64 = 0.1;
+const RESET_PERCENTAGE: f64 = 0.0;
+
+/// Success rate the 1/5th rule steers towards
+const TARGET_SUCCESS_RATE: f64 = 0.2;
+/// The 1/5th rule never moves sigma further than this factor away from the schedule
+const MAX_SCALE: f64 = 1000.0;
+
+/// Per-gene mutation of the weights and biases of a member: every gene mutates with probability
+/// `gene_percentage`, either redrawn in [-1, 1) or moved by Gaussian noise of deviation `sigma`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MutationConfig {
+    /// Chance of each weight and bias to mutate, in percent
+    pub gene_percentage: f64,
+    /// Standard deviation of the Gaussian perturbation
+    pub sigma: f64,
+    /// Chance of a mutating gene to be redrawn instead of perturbed, in percent
+    pub reset_percentage: f64,
+    /// Mutated genes are kept within [-clamp, clamp]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clamp: Option<f64>,
+    /// Overrides for the first layers, by position; missing entries use the settings above
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub layers: Vec<LayerMutation>,
+    /// How `sigma` decays over the generations
+    pub schedule: SigmaSchedule,
+    /// How `sigma` reacts to the progress of the run
+    pub adaptation: Adaptation,
+}
+
+/// Decay of the mutation strength with the generation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum SigmaSchedule {
+    #[default]
+    Constant,
+    /// From `sigma` to `final_sigma` in `generations`, constant afterwards
+    Linear { final_sigma: f64, generations: usize },
+    /// Multiplied by `decay` every generation
+    Exponential { decay: f64 },
+    /// Multiplied by `factor` every `every` generations
+    Step { every: usize, factor: f64 },
+}
+
+impl SigmaSchedule {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            SigmaSchedule::Linear { final_sigma, .. } if !final_sigma.is_finite() || final_sigma < 0.0 => {
+                Err(format!("Linear schedule final_sigma must be zero or positive, got {final_sigma}"))
+            }
+            SigmaSchedule::Linear { generations: 0, .. } => Err("Linear schedule needs at least 1 generation".to_string()),
+            SigmaSchedule::Exponential { decay } if decay <= 0.0 || decay > 1.0 => {
+                Err(format!("Exponential schedule decay must be in (0, 1], got {decay}"))
+            }
+            SigmaSchedule::Step { every: 0, .. } => Err("Step schedule needs every to be at least 1".to_string()),
+            SigmaSchedule::Step { factor, .. } if !factor.is_finite() || factor <= 0.0 => {
+                Err(format!("Step schedule factor must be positive, got {factor}"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Sigma at `generation` for a run starting at `sigma`
+    pub fn sigma(&self, sigma: f64, generation: usize) -> f64 {
+        match *self {
+            SigmaSchedule::Constant => sigma,
+            SigmaSchedule::Linear { final_sigma, generations } => {
+                let progress = (generation as f64 / generations as f64).min(1.0);
+                sigma + (final_sigma - sigma) * progress
+            }
+            SigmaSchedule::Exponential { decay } => sigma * decay.powf(generation as f64),
+            SigmaSchedule::Step { every, factor } => sigma * factor.powf((generation / every) as f64),
+        }
+    }
+}
+
+/// Feedback on the mutation strength, on top of the schedule
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Adaptation {
+    #[default]
+    None,
+    /// Rechenberg's 1/5th success rule: after each evaluation the population wide sigma is divided
+    /// by `factor` when more than a fifth of the crossovers beat their best parent, and multiplied
+    /// by it when fewer did
+    OneFifth { factor: f64 },
+    /// Every member carries its own sigma: the mean of its parents', times exp(`tau` N(0, 1)), and
+    /// never below `min_sigma`. Members without one, like random members, count with the scheduled sigma.
+    SelfAdaptive { tau: f64, min_sigma: f64 },
+}
+
+impl Adaptation {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            Adaptation::OneFifth { factor } if factor <= 0.0 || factor >= 1.0 => {
+                Err(format!("1/5th rule factor must be between 0 and 1, got {factor}"))
+            }
+            Adaptation::SelfAdaptive { tau, .. } if !tau.is_finite() || tau <= 0.0 => {
+                Err(format!("Self-adaptive tau must be positive, got {tau}"))
+            }
+            Adaptation::SelfAdaptive { min_sigma, .. } if !min_sigma.is_finite() || min_sigma < 0.0 => {
+                Err(format!("Self-adaptive min_sigma must be zero or positive, got {min_sigma}"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Settings of one layer that differ from the rest of the network
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayerMutation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gene_percentage: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sigma: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clamp: Option<f64>,
+}
+
+impl Default for MutationConfig {
+    fn default() -> Self {
+        MutationConfig {
+            gene_percentage: GENE_PERCENTAGE,
+            sigma: SIGMA,
+            reset_percentage: RESET_PERCENTAGE,
+            clamp: None,
+            layers: Vec::new(),
+            schedule: SigmaSchedule::default(),
+            adaptation: Adaptation::default(),
+        }
+    }
+}
+
+impl MutationConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let layers = self.layers.iter().enumerate().map(|(i, l)| (format!("layer {i} "), l.gene_percentage, l.sigma, l.clamp));
+        for (scope, gene_percentage, sigma, clamp) in
+            std::iter::once((String::new(), Some(self.gene_percentage), Some(self.sigma), self.clamp)).chain(layers)
+        {
+            if let Some(rate) = gene_percentage.filter(|rate| !(0.0..=100.0).contains(rate)) {
+                return Err(format!("{scope}gene_percentage must be a percentage, got {rate}"));
+            }
+            if let Some(sigma) = sigma.filter(|sigma| !sigma.is_finite() || *sigma < 0.0) {
+                return Err(format!("{scope}sigma must be zero or positive, got {sigma}"));
+            }
+            if let Some(clamp) = clamp.filter(|clamp| clamp.is_nan() || *clamp <= 0.0) {
+                return Err(format!("{scope}clamp must be positive, got {clamp}"));
+            }
+        }
+        if !(0.0..=100.0).contains(&self.reset_percentage) {
+            return Err(format!("reset_percentage must be a percentage, got {}", self.reset_percentage));
+        }
+        self.schedule.validate()?;
+        self.adaptation.validate()
+    }
+
+    /// Scheduled sigma at `generation`, times the `scale` of the 1/5th rule
+    pub fn scheduled_sigma(&self, generation: usize, scale: f64) -> f64 {
+        self.schedule.sigma(self.sigma, generation) * scale
+    }
+
+    /// Mutates a crossover of `parents` with the population wide `sigma`, or with its own sigma when
+    /// self-adaptive, and remembers the fitness it has to beat
+    pub fn mutate_offspring(&self, child: &mut Member, parents: [&Member; 2], sigma: f64, rng: &mut impl Rng) -> usize {
+        child.parent_fitness = Some(parents[0].fitness.max(parents[1].fitness));
+        child.sigma = match self.adaptation {
+            Adaptation::SelfAdaptive { tau, min_sigma } => {
+                let inherited = parents.iter().map(|p| p.sigma.unwrap_or(sigma)).sum::<f64>() / 2.0;
+                let step: f64 = Normal::new(0.0, tau).expect("Validated tau").sample(rng);
+                Some((inherited * step.exp()).max(min_sigma))
+            }
+            _ => None,
+        };
+        self.mutate(child, child.sigma.unwrap_or(sigma), rng)
+    }
+
+    /// Next scale of the 1/5th rule once `members` are evaluated, consuming their parent fitness.
+    /// Other adaptations keep `scale` as is.
+    pub fn adapt(&self, scale: f64, members: &mut [Member]) -> f64 {
+        let outcomes: Vec<bool> = members
+            .iter_mut()
+            .filter_map(|m| m.parent_fitness.take().map(|parent| m.fitness > parent))
+            .collect();
+        let Adaptation::OneFifth { factor } = self.adaptation else {
+            return scale;
+        };
+        if outcomes.is_empty() {
+            return scale;
+        }
+        let success_rate = outcomes.iter().filter(|&&improved| improved).count() as f64 / outcomes.len() as f64;
+        let scale = if success_rate > TARGET_SUCCESS_RATE {
+            scale / factor
+        } else if success_rate < TARGET_SUCCESS_RATE {
+            scale * factor
+        } else {
+            scale
+        };
+        scale.clamp(1.0 / MAX_SCALE, MAX_SCALE)
+    }
+
+    /// Mutates the parameters of `member` in place with network wide deviation `sigma` and returns how
+    /// many genes changed. Layer overrides of sigma keep their ratio to the configured one.
+    pub fn mutate(&self, member: &mut Member, sigma: f64, rng: &mut impl Rng) -> usize {
+        let mut mutated = 0;
+        for (layer, (weights, biases)) in member.weights.iter_mut().zip(member.biases.iter_mut()).enumerate() {
+            let overrides = self.layers.get(layer).copied().unwrap_or_default();
+            let gene_percentage = overrides.gene_percentage.unwrap_or(self.gene_percentage);
+            let clamp = overrides.clamp.or(self.clamp);
+            let layer_sigma = match overrides.sigma {
+                Some(layer_sigma) if self.sigma > 0.0 => layer_sigma * sigma / self.sigma,
+                Some(layer_sigma) => layer_sigma,
+                None => sigma,
+            };
+            let noise = Normal::new(0.0, layer_sigma).expect("Validated sigma");
+
+            for gene in weights.iter_mut().chain(biases.iter_mut()) {
+                if rng.random_range(0.0..100.0) >= gene_percentage {
+                    continue;
+                }
+                *gene = if rng.random_range(0.0..100.0) < self.reset_percentage {
+                    rng.random_range(-1.0..1.0)
+                } else {
+                    *gene + noise.sample(rng)
+                };
+                if let Some(limit) = clamp {
+                    *gene = gene.clamp(-limit, limit);
+                }
+                mutated += 1;
+            }
+        }
+        mutated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nn_architecture::NN_Architecture;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn member() -> Member {
+        Member::new(NN_Architecture::new(), Some([3; 32]), 0)
+    }
+
+    fn changed(before: &Member, after: &Member, layer: usize) -> usize {
+        let weights = before.weights[layer].iter().zip(after.weights[layer].iter());
+        let biases = before.biases[layer].iter().zip(after.biases[layer].iter());
+        weights.chain(biases).filter(|(a, b)| a != b).count()
+    }
+
+    #[test]
+    fn test_gene_rate_and_sigma() {
+        let original = member();
+        let genes: usize = original.weights.iter().chain(&original.biases).map(|a| a.len()).sum();
+        let mutation = MutationConfig { gene_percentage: 10.0, sigma: 0.05, ..MutationConfig::default() };
+
+        let mut mutant = original.clone();
+        let mutated = mutation.mutate(&mut mutant, mutation.sigma, &mut StdRng::seed_from_u64(1));
+        let fraction = mutated as f64 / genes as f64;
+        assert!((fraction - 0.1).abs() < 0.02, "{fraction}");
+
+        let deltas: Vec<f64> = (0..original.weights.len())
+            .flat_map(|i| (&mutant.weights[i] - &original.weights[i]).into_iter())
+            .filter(|d| *d != 0.0)
+            .collect();
+        let deviation = (deltas.iter().map(|d| d * d).sum::<f64>() / deltas.len() as f64).sqrt();
+        assert!((deviation - 0.05).abs() < 0.01, "{deviation}");
+    }
+
+    #[test]
+    fn test_reset_and_clamp() {
+        let mutation = MutationConfig {
+            gene_percentage: 100.0,
+            sigma: 10.0,
+            clamp: Some(0.5),
+            ..MutationConfig::default()
+        };
+        let mut mutant = member();
+        mutation.mutate(&mut mutant, mutation.sigma, &mut StdRng::seed_from_u64(2));
+        assert!(mutant.weights.iter().chain(&mutant.biases).all(|a| a.iter().all(|g| g.abs() <= 0.5)));
+
+        let reset = MutationConfig { gene_percentage: 100.0, reset_percentage: 100.0, ..MutationConfig::default() };
+        mutant = member();
+        reset.mutate(&mut mutant, reset.sigma, &mut StdRng::seed_from_u64(3));
+        assert!(mutant.weights.iter().all(|a| a.iter().all(|g| (-1.0..1.0).contains(g))));
+    }
+
+    #[test]
+    fn test_layer_overrides() {
+        let mutation = MutationConfig {
+            gene_percentage: 100.0,
+            layers: vec![LayerMutation { gene_percentage: Some(0.0), ..LayerMutation::default() }],
+            ..MutationConfig::default()
+        };
+        let original = member();
+        let mut mutant = original.clone();
+        mutation.mutate(&mut mutant, mutation.sigma, &mut StdRng::seed_from_u64(4));
+
+        assert_eq!(changed(&original, &mutant, 0), 0);
+        assert_eq!(changed(&original, &mutant, 1), original.weights[1].len() + original.biases[1].len());
+    }
+
+    #[test]
+    fn test_invalid_settings_are_rejected() {
+        assert!(MutationConfig::default().validate().is_ok());
+        assert!(MutationConfig { gene_percentage: 120.0, ..MutationConfig::default() }.validate().is_err());
+        assert!(MutationConfig { sigma: -1.0, ..MutationConfig::default() }.validate().is_err());
+        assert!(MutationConfig { clamp: Some(0.0), ..MutationConfig::default() }.validate().is_err());
+        let layer = LayerMutation { sigma: Some(f64::NAN), ..LayerMutation::default() };
+        let err = MutationConfig { layers: vec![layer], ..MutationConfig::default() }.validate().unwrap_err();
+        assert!(err.starts_with("layer 0 sigma"), "{err}");
+
+        let schedule = SigmaSchedule::Exponential { decay: 1.5 };
+        assert!(MutationConfig { schedule, ..MutationConfig::default() }.validate().is_err());
+        let adaptation = Adaptation::OneFifth { factor: 1.0 };
+        assert!(MutationConfig { adaptation, ..MutationConfig::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_schedules() {
+        let linear = SigmaSchedule::Linear { final_sigma: 0.0, generations: 100 };
+        assert_eq!(linear.sigma(0.2, 0), 0.2);
+        assert!((linear.sigma(0.2, 50) - 0.1).abs() < 1e-12);
+        assert_eq!(linear.sigma(0.2, 3000), 0.0);
+
+        let exponential = SigmaSchedule::Exponential { decay: 0.5 };
+        assert_eq!(exponential.sigma(0.2, 2), 0.05);
+
+        let step = SigmaSchedule::Step { every: 10, factor: 0.5 };
+        assert_eq!(step.sigma(0.2, 9), 0.2);
+        assert_eq!(step.sigma(0.2, 25), 0.05);
+        assert_eq!(SigmaSchedule::Constant.sigma(0.2, 3000), 0.2);
+    }
+
+    #[test]
+    fn test_one_fifth_rule() {
+        let mutation = MutationConfig { adaptation: Adaptation::OneFifth { factor: 0.5 }, ..MutationConfig::default() };
+        let offspring = |improved: usize| -> Vec<Member> {
+            (0..10)
+                .map(|i| {
+                    let mut m = member();
+                    m.fitness = 1.0;
+                    m.parent_fitness = Some(if i < improved { 0.0 } else { 2.0 });
+                    m
+                })
+                .collect()
+        };
+
+        assert_eq!(mutation.adapt(1.0, &mut offspring(5)), 2.0);
+        assert_eq!(mutation.adapt(1.0, &mut offspring(2)), 1.0);
+        let mut failing = offspring(0);
+        assert_eq!(mutation.adapt(1.0, &mut failing), 0.5);
+        // Counted once, elites carried over do not weigh on the next generation
+        assert!(failing.iter().all(|m| m.parent_fitness.is_none()));
+        assert_eq!(mutation.adapt(1.0, &mut failing), 1.0);
+    }
+
+    #[test]
+    fn test_self_adaptive_sigma_is_inherited() {
+        let mutation = MutationConfig {
+            adaptation: Adaptation::SelfAdaptive { tau: 0.1, min_sigma: 0.01 },
+            ..MutationConfig::default()
+        };
+        let mut parent1 = member();
+        parent1.sigma = Some(0.4);
+        let parent2 = member();
+        let mut rng = StdRng::seed_from_u64(5);
+
+        let mut child = member();
+        mutation.mutate_offspring(&mut child, [&parent1, &parent2], 0.2, &mut rng);
+        // Mean of 0.4 and the fallback 0.2, moved by a log-normal step
+        let sigma = child.sigma.unwrap();
+        assert!(sigma > 0.3 * (-0.5f64).exp() && sigma < 0.3 * 0.5f64.exp(), "{sigma}");
+
+        parent1.sigma = Some(0.0);
+        let mut child = member();
+        mutation.mutate_offspring(&mut child, [&parent1, &parent1], 0.2, &mut rng);
+        assert_eq!(child.sigma, Some(0.01));
+        assert_eq!(child.parent_fitness, Some(0.0));
+    }
+}
@@ -0,0 +1,1068 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+use ndarray::Array2;
+use rand::{Rng, SeedableRng, rng, rngs::StdRng};
+use rand_distr::{Distribution, Normal};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, ExperimentConfig};
+use crate::environment::{run_episode, Agent, Observation};
+use crate::member::activate;
+use crate::nn_architecture::Activation;
+use crate::observation::ObservationEncoder;
+use crate::population::draw_episode_seeds;
+use crate::snakegame::{GameConfig, OutcomeCounts, Snakegame};
+use crate::stats::{Evaluated, GenerationStats};
+
+const COMPATIBILITY_THRESHOLD: f64 = 3.0;
+const EXCESS_COEFFICIENT: f64 = 1.0;
+const DISJOINT_COEFFICIENT: f64 = 1.0;
+const WEIGHT_COEFFICIENT: f64 = 0.4;
+
+const WEIGHT_MUTATION_PERCENTAGE: usize = 80;
+const WEIGHT_REPLACE_PERCENTAGE: usize = 10; // Of the mutated weights, the rest are perturbed
+const WEIGHT_PERTURBATION: f64 = 0.5;
+const ADD_CONNECTION_PERCENTAGE: usize = 5;
+const ADD_NODE_PERCENTAGE: usize = 3;
+
+const CROSSOVER_PERCENTAGE: usize = 75;
+const DISABLE_INHERITED_PERCENTAGE: usize = 75;
+const SURVIVAL_PERCENTAGE: usize = 20;
+const STAGNATION_LIMIT: usize = 15; // Generations without improvement before a species is dropped
+
+const ADD_CONNECTION_ATTEMPTS: usize = 20;
+
+/// Which kind of genome an experiment evolves
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum GenomeMode {
+    /// `Member`s with the fixed layers of the experiment's architecture
+    #[default]
+    Layers,
+    /// NEAT `Genome`s growing their own topology from a direct input to output network
+    Neat(NeatConfig),
+}
+
+/// Rates and thresholds of NEAT, rates in percent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NeatConfig {
+    /// Genomes closer than this belong to the same species
+    pub compatibility_threshold: f64,
+    pub excess_coefficient: f64,
+    pub disjoint_coefficient: f64,
+    pub weight_coefficient: f64,
+    /// Chance of every connection weight to mutate
+    pub weight_mutation_percentage: usize,
+    /// Chance of a mutating weight to be replaced instead of perturbed
+    pub weight_replace_percentage: usize,
+    /// Standard deviation of the perturbations
+    pub weight_perturbation: f64,
+    pub add_connection_percentage: usize,
+    pub add_node_percentage: usize,
+    /// Chance of an offspring to have two parents, the rest are mutated copies
+    pub crossover_percentage: usize,
+    /// Chance of a gene disabled in either parent to stay disabled
+    pub disable_inherited_percentage: usize,
+    /// Best members of each species allowed to breed
+    pub survival_percentage: usize,
+    pub stagnation_limit: usize,
+    pub hidden_activation: Activation,
+    pub output_activation: Activation,
+}
+
+impl Default for NeatConfig {
+    fn default() -> Self {
+        NeatConfig {
+            compatibility_threshold: COMPATIBILITY_THRESHOLD,
+            excess_coefficient: EXCESS_COEFFICIENT,
+            disjoint_coefficient: DISJOINT_COEFFICIENT,
+            weight_coefficient: WEIGHT_COEFFICIENT,
+            weight_mutation_percentage: WEIGHT_MUTATION_PERCENTAGE,
+            weight_replace_percentage: WEIGHT_REPLACE_PERCENTAGE,
+            weight_perturbation: WEIGHT_PERTURBATION,
+            add_connection_percentage: ADD_CONNECTION_PERCENTAGE,
+            add_node_percentage: ADD_NODE_PERCENTAGE,
+            crossover_percentage: CROSSOVER_PERCENTAGE,
+            disable_inherited_percentage: DISABLE_INHERITED_PERCENTAGE,
+            survival_percentage: SURVIVAL_PERCENTAGE,
+            stagnation_limit: STAGNATION_LIMIT,
+            hidden_activation: Activation::Tanh,
+            output_activation: Activation::Sigmoid,
+        }
+    }
+}
+
+impl NeatConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let rates = [
+            ("weight_mutation_percentage", self.weight_mutation_percentage),
+            ("weight_replace_percentage", self.weight_replace_percentage),
+            ("add_connection_percentage", self.add_connection_percentage),
+            ("add_node_percentage", self.add_node_percentage),
+            ("crossover_percentage", self.crossover_percentage),
+            ("disable_inherited_percentage", self.disable_inherited_percentage),
+            ("survival_percentage", self.survival_percentage),
+        ];
+        if let Some((name, rate)) = rates.iter().find(|(_, rate)| *rate > 100) {
+            return Err(format!("{name} must be a percentage, got {rate}"));
+        }
+        if self.survival_percentage == 0 {
+            return Err("survival_percentage must be at least 1, offspring are bred from survivors".to_string());
+        }
+        if self.compatibility_threshold <= 0.0 {
+            return Err("compatibility_threshold must be positive".to_string());
+        }
+        if self.weight_perturbation <= 0.0 || !self.weight_perturbation.is_finite() {
+            return Err("weight_perturbation must be a positive standard deviation".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NodeKind {
+    Input,
+    /// Always outputs 1
+    Bias,
+    Hidden,
+    Output,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeGene {
+    pub id: usize,
+    pub kind: NodeKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionGene {
+    /// Historical marking, the same structural change gets the same number in every genome
+    pub innovation: usize,
+    pub from: usize,
+    pub to: usize,
+    pub weight: f64,
+    pub enabled: bool,
+}
+
+/// Hands out node ids and innovation numbers. Changes repeated within a generation reuse the
+/// numbers of their first occurrence, so identical mutations line up in crossover.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InnovationTracker {
+    next_node: usize,
+    next_innovation: usize,
+    #[serde(skip)]
+    connections: HashMap<(usize, usize), usize>,
+    #[serde(skip)]
+    splits: HashMap<usize, usize>,
+}
+
+impl InnovationTracker {
+    /// Reserves the ids of the minimal genome: inputs, then the bias, then the outputs, fully
+    /// connected in that order
+    pub fn new(inputs: usize, outputs: usize) -> Self {
+        InnovationTracker {
+            next_node: inputs + 1 + outputs,
+            next_innovation: (inputs + 1) * outputs,
+            connections: HashMap::new(),
+            splits: HashMap::new(),
+        }
+    }
+
+    /// Innovation number of a connection from `from` to `to`
+    pub fn connection(&mut self, from: usize, to: usize) -> usize {
+        *self.connections.entry((from, to)).or_insert_with(|| {
+            self.next_innovation += 1;
+            self.next_innovation - 1
+        })
+    }
+
+    /// Id of the node splitting the connection `innovation`
+    pub fn split(&mut self, innovation: usize) -> usize {
+        *self.splits.entry(innovation).or_insert_with(|| {
+            self.next_node += 1;
+            self.next_node - 1
+        })
+    }
+
+    /// Forgets the changes of the finished generation
+    pub fn new_generation(&mut self) {
+        self.connections.clear();
+        self.splits.clear();
+    }
+}
+
+/// NEAT genome: a feedforward network of any topology, played like a `Member`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Genome {
+    /// Sorted by id
+    pub nodes: Vec<NodeGene>,
+    /// Sorted by innovation number
+    pub connections: Vec<ConnectionGene>,
+    pub hidden_activation: Activation,
+    pub output_activation: Activation,
+    pub fitness: f64,
+    pub generation: usize,
+    #[serde(default)]
+    pub outcomes: OutcomeCounts,
+    #[serde(default)]
+    pub apples_eaten: usize,
+    #[serde(default)]
+    pub steps_survived: usize,
+}
+
+impl Genome {
+    /// Every input and the bias connected straight to every output with N(0, 1) weights, the
+    /// starting point of NEAT. Ids and innovations match `InnovationTracker::new`.
+    pub fn minimal(inputs: usize, outputs: usize, config: &NeatConfig, rng: &mut impl Rng, generation: usize) -> Self {
+        let normal: Normal<f64> = Normal::new(0.0, 1.0).unwrap();
+        let mut nodes: Vec<NodeGene> = (0..inputs).map(|id| NodeGene { id, kind: NodeKind::Input }).collect();
+        nodes.push(NodeGene { id: inputs, kind: NodeKind::Bias });
+        nodes.extend((0..outputs).map(|o| NodeGene { id: inputs + 1 + o, kind: NodeKind::Output }));
+
+        let mut connections: Vec<ConnectionGene> = Vec::with_capacity((inputs + 1) * outputs);
+        for from in 0..=inputs {
+            for o in 0..outputs {
+                connections.push(ConnectionGene {
+                    innovation: from * outputs + o,
+                    from,
+                    to: inputs + 1 + o,
+                    weight: normal.sample(rng),
+                    enabled: true,
+                });
+            }
+        }
+
+        Genome {
+            nodes,
+            connections,
+            hidden_activation: config.hidden_activation.clone(),
+            output_activation: config.output_activation.clone(),
+            fitness: 0.0,
+            generation,
+            outcomes: OutcomeCounts::default(),
+            apples_eaten: 0,
+            steps_survived: 0,
+        }
+    }
+
+    /// Number of nodes of `kind`
+    pub fn count(&self, kind: NodeKind) -> usize {
+        self.nodes.iter().filter(|n| n.kind == kind).count()
+    }
+
+    /// Checks the layout `feedforward` and `distance` rely on: node ids sorted and unique with
+    /// every input before the bias, innovation numbers strictly increasing, every connection
+    /// joining two nodes of the genome and no connection, enabled or not, closing a cycle. A bad
+    /// checkpoint is then reported on load instead of mid-game.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(w) = self.nodes.windows(2).find(|w| w[0].id >= w[1].id) {
+            return Err(format!("Node ids must be sorted and unique, got {} before {}", w[0].id, w[1].id));
+        }
+        if let Some(bias) = self.nodes.iter().position(|n| n.kind == NodeKind::Bias)
+            && let Some(input) = self.nodes[bias..].iter().find(|n| n.kind == NodeKind::Input)
+        {
+            return Err(format!("Input node {} comes after the bias", input.id));
+        }
+        if let Some(w) = self.connections.windows(2).find(|w| w[0].innovation >= w[1].innovation) {
+            return Err(format!(
+                "Innovation numbers must be strictly increasing, got {} before {}",
+                w[0].innovation, w[1].innovation
+            ));
+        }
+        for c in &self.connections {
+            for id in [c.from, c.to] {
+                if self.kind(id).is_none() {
+                    return Err(format!("Connection {} uses node {id}, which the genome does not have", c.innovation));
+                }
+            }
+        }
+        if let Some(c) = self.connections.iter().find(|c| self.reaches(c.to, c.from)) {
+            return Err(format!("Connection {} from node {} to {} closes a cycle", c.innovation, c.from, c.to));
+        }
+        Ok(())
+    }
+
+    fn kind(&self, id: usize) -> Option<NodeKind> {
+        self.nodes.iter().find(|n| n.id == id).map(|n| n.kind)
+    }
+
+    /// Hidden and output nodes ordered so every node comes after the nodes feeding it
+    fn evaluation_order(&self) -> Vec<usize> {
+        let mut pending: HashMap<usize, usize> = HashMap::new();
+        for c in self.connections.iter().filter(|c| c.enabled) {
+            *pending.entry(c.to).or_insert(0) += 1;
+        }
+        let mut ready: Vec<usize> = self.nodes.iter().filter(|n| !pending.contains_key(&n.id)).map(|n| n.id).collect();
+        let mut order: Vec<usize> = Vec::with_capacity(self.nodes.len());
+        while let Some(id) = ready.pop() {
+            order.push(id);
+            for c in self.connections.iter().filter(|c| c.enabled && c.from == id) {
+                let count = pending.get_mut(&c.to).expect("Every target is pending");
+                *count -= 1;
+                if *count == 0 {
+                    ready.push(c.to);
+                }
+            }
+        }
+        order.retain(|&id| matches!(self.kind(id), Some(NodeKind::Hidden | NodeKind::Output)));
+        order
+    }
+
+    /// Column of output values for a column of inputs
+    pub fn feedforward(&self, input: &Observation) -> Array2<f64> {
+        let mut values: HashMap<usize, f64> = HashMap::new();
+        let mut outputs: Vec<usize> = Vec::new();
+        for node in &self.nodes {
+            match node.kind {
+                NodeKind::Input => {
+                    values.insert(node.id, input[[values.len(), 0]]);
+                }
+                NodeKind::Bias => {
+                    values.insert(node.id, 1.0);
+                }
+                NodeKind::Output => outputs.push(node.id),
+                NodeKind::Hidden => {}
+            }
+        }
+
+        for id in self.evaluation_order() {
+            let sum: f64 = self
+                .connections
+                .iter()
+                .filter(|c| c.enabled && c.to == id)
+                .map(|c| c.weight * values.get(&c.from).copied().unwrap_or(0.0))
+                .sum();
+            let value = match self.kind(id) {
+                Some(NodeKind::Hidden) => activate(&Array2::from_elem((1, 1), sum), self.hidden_activation.clone())[[0, 0]],
+                _ => sum,
+            };
+            values.insert(id, value);
+        }
+
+        let raw: Array2<f64> = Array2::from_shape_fn((outputs.len(), 1), |(i, _)| values[&outputs[i]]);
+        activate(&raw, self.output_activation.clone())
+    }
+
+    /// Whether `goal` can be reached from `start` following connections, disabled ones included
+    /// since crossover may enable them again
+    fn reaches(&self, start: usize, goal: usize) -> bool {
+        let mut stack: Vec<usize> = vec![start];
+        let mut seen: HashSet<usize> = HashSet::new();
+        while let Some(id) = stack.pop() {
+            if id == goal {
+                return true;
+            }
+            if seen.insert(id) {
+                stack.extend(self.connections.iter().filter(|c| c.from == id).map(|c| c.to));
+            }
+        }
+        false
+    }
+
+    /// Connects two unconnected nodes, never into an input or out of an output and never closing
+    /// a cycle. Returns whether a connection was added.
+    pub fn add_connection(&mut self, tracker: &mut InnovationTracker, rng: &mut impl Rng) -> bool {
+        let sources: Vec<usize> = self.nodes.iter().filter(|n| n.kind != NodeKind::Output).map(|n| n.id).collect();
+        let targets: Vec<usize> = self
+            .nodes
+            .iter()
+            .filter(|n| matches!(n.kind, NodeKind::Hidden | NodeKind::Output))
+            .map(|n| n.id)
+            .collect();
+        if sources.is_empty() || targets.is_empty() {
+            return false;
+        }
+
+        for _ in 0..ADD_CONNECTION_ATTEMPTS {
+            let from = sources[rng.random_range(0..sources.len())];
+            let to = targets[rng.random_range(0..targets.len())];
+            if from == to || self.connections.iter().any(|c| c.from == from && c.to == to) || self.reaches(to, from) {
+                continue;
+            }
+            let normal: Normal<f64> = Normal::new(0.0, 1.0).unwrap();
+            self.insert_connection(ConnectionGene {
+                innovation: tracker.connection(from, to),
+                from,
+                to,
+                weight: normal.sample(rng),
+                enabled: true,
+            });
+            return true;
+        }
+        false
+    }
+
+    /// Splits an enabled connection with a new hidden node. The incoming connection has weight 1
+    /// and the outgoing one the old weight, so the network barely changes. Returns whether a node
+    /// was added.
+    pub fn add_node(&mut self, tracker: &mut InnovationTracker, rng: &mut impl Rng) -> bool {
+        let enabled: Vec<usize> = (0..self.connections.len()).filter(|&i| self.connections[i].enabled).collect();
+        if enabled.is_empty() {
+            return false;
+        }
+        let old: ConnectionGene = self.connections[enabled[rng.random_range(0..enabled.len())]];
+        let node: usize = tracker.split(old.innovation);
+        if self.kind(node).is_some() {
+            return false;
+        }
+
+        self.connections.iter_mut().find(|c| c.innovation == old.innovation).unwrap().enabled = false;
+        let position = self.nodes.partition_point(|n| n.id < node);
+        self.nodes.insert(position, NodeGene { id: node, kind: NodeKind::Hidden });
+        self.insert_connection(ConnectionGene {
+            innovation: tracker.connection(old.from, node),
+            from: old.from,
+            to: node,
+            weight: 1.0,
+            enabled: true,
+        });
+        self.insert_connection(ConnectionGene {
+            innovation: tracker.connection(node, old.to),
+            from: node,
+            to: old.to,
+            weight: old.weight,
+            enabled: true,
+        });
+        true
+    }
+
+    fn insert_connection(&mut self, connection: ConnectionGene) {
+        let position = self.connections.partition_point(|c| c.innovation < connection.innovation);
+        self.connections.insert(position, connection);
+    }
+
+    /// Perturbs or replaces weights, then maybe adds a node and a connection
+    pub fn mutate(&mut self, config: &NeatConfig, tracker: &mut InnovationTracker, rng: &mut impl Rng) {
+        let normal: Normal<f64> = Normal::new(0.0, 1.0).unwrap();
+        for c in self.connections.iter_mut() {
+            if rng.random_range(0..100) < config.weight_mutation_percentage {
+                if rng.random_range(0..100) < config.weight_replace_percentage {
+                    c.weight = normal.sample(rng);
+                } else {
+                    c.weight += config.weight_perturbation * normal.sample(rng);
+                }
+            }
+        }
+        if rng.random_range(0..100) < config.add_node_percentage {
+            self.add_node(tracker, rng);
+        }
+        if rng.random_range(0..100) < config.add_connection_percentage {
+            self.add_connection(tracker, rng);
+        }
+    }
+
+    /// Offspring of `fitter` and `other`: matching genes come from either parent at random, disjoint
+    /// and excess genes only from `fitter`, whose nodes it keeps
+    pub fn crossover(fitter: &Genome, other: &Genome, config: &NeatConfig, rng: &mut impl Rng, generation: usize) -> Genome {
+        let other_genes: HashMap<usize, &ConnectionGene> = other.connections.iter().map(|c| (c.innovation, c)).collect();
+        let connections: Vec<ConnectionGene> = fitter
+            .connections
+            .iter()
+            .map(|gene| match other_genes.get(&gene.innovation) {
+                Some(matching) => {
+                    let mut child = if rng.random_bool(0.5) { *gene } else { **matching };
+                    if !gene.enabled || !matching.enabled {
+                        child.enabled = rng.random_range(0..100) >= config.disable_inherited_percentage;
+                    }
+                    child
+                }
+                None => *gene,
+            })
+            .collect();
+
+        Genome {
+            nodes: fitter.nodes.clone(),
+            connections,
+            fitness: 0.0,
+            generation,
+            outcomes: OutcomeCounts::default(),
+            apples_eaten: 0,
+            steps_survived: 0,
+            ..fitter.clone()
+        }
+    }
+
+    /// NEAT compatibility distance: excess and disjoint genes relative to the larger genome, plus
+    /// the mean weight difference of matching genes
+    pub fn distance(&self, other: &Genome, config: &NeatConfig) -> f64 {
+        let (mut i, mut j) = (0, 0);
+        let (mut disjoint, mut matching, mut weight_difference) = (0usize, 0usize, 0.0);
+        let (a, b) = (&self.connections, &other.connections);
+        while i < a.len() && j < b.len() {
+            match a[i].innovation.cmp(&b[j].innovation) {
+                std::cmp::Ordering::Equal => {
+                    weight_difference += (a[i].weight - b[j].weight).abs();
+                    matching += 1;
+                    i += 1;
+                    j += 1;
+                }
+                std::cmp::Ordering::Less => {
+                    disjoint += 1;
+                    i += 1;
+                }
+                std::cmp::Ordering::Greater => {
+                    disjoint += 1;
+                    j += 1;
+                }
+            }
+        }
+        let excess = (a.len() - i) + (b.len() - j);
+        let genes = a.len().max(b.len()).max(1) as f64;
+        let mean_weight_difference = if matching == 0 { 0.0 } else { weight_difference / matching as f64 };
+
+        config.excess_coefficient * excess as f64 / genes
+            + config.disjoint_coefficient * disjoint as f64 / genes
+            + config.weight_coefficient * mean_weight_difference
+    }
+
+    /// Plays one game per seed and sets the fitness to the average score
+    pub fn iterate_to_update_fitness(&mut self, seeds: &[u64], game_config: &GameConfig) {
+        self.outcomes = OutcomeCounts::default();
+        self.apples_eaten = 0;
+        self.steps_survived = 0;
+
+        let mut sum: f64 = 0.0;
+        for &seed in seeds {
+            let mut sg = Snakegame::with_seed(*game_config, seed);
+            let episode = run_episode(&mut sg, self, seed);
+            self.outcomes.record(episode.info.outcome.expect("A finished episode has an outcome"));
+            self.apples_eaten += episode.info.apples_eaten;
+            self.steps_survived += episode.info.steps;
+            sum += episode.total_reward;
+        }
+        // No games played leaves the fitness at 0 instead of NaN, like `Member`
+        self.fitness = if seeds.is_empty() { 0.0 } else { sum / seeds.len() as f64 };
+    }
+}
+
+impl Agent for Genome {
+    fn act(&mut self, observation: &Observation) -> usize {
+        self.feedforward(observation)
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(idx, _)| idx)
+            .unwrap_or(0)
+    }
+}
+
+impl Evaluated for Genome {
+    fn fitness(&self) -> f64 {
+        self.fitness
+    }
+
+    fn generation(&self) -> usize {
+        self.generation
+    }
+
+    fn outcomes(&self) -> &OutcomeCounts {
+        &self.outcomes
+    }
+
+    fn apples_eaten(&self) -> usize {
+        self.apples_eaten
+    }
+
+    fn steps_survived(&self) -> usize {
+        self.steps_survived
+    }
+
+    fn parameters(&self) -> Vec<f64> {
+        self.connections.iter().map(|c| c.weight).collect()
+    }
+}
+
+/// Genomes close enough to compete with each other rather than with the whole population
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Species {
+    pub id: usize,
+    /// New genomes join the first species whose representative is within the threshold
+    pub representative: Genome,
+    /// Indices of the current genomes in the species
+    #[serde(skip)]
+    pub members: Vec<usize>,
+    pub best_fitness: f64,
+    pub stagnant_generations: usize,
+}
+
+/// A generation of NEAT genomes together with the run settings, serialised as a checkpoint
+#[derive(Serialize, Deserialize)]
+pub struct NeatPopulation {
+    genomes: Vec<Genome>,
+    species: Vec<Species>,
+    innovations: InnovationTracker,
+    next_species_id: usize,
+    config: ExperimentConfig,
+    generation: usize,
+    episode_seeds: Vec<u64>,
+    /// Seed of the next rng drawn by `next_rng`, so a resumed run continues the same random stream
+    rng_state: u64,
+}
+
+impl NeatPopulation {
+    /// `training.population_size` minimal genomes sized for the observation and actions of the game
+    pub fn new(config: ExperimentConfig) -> Self {
+        let inputs: usize = config.game.observation.size(&config.game);
+        let outputs: usize = config.game.action_mode.size();
+        let mut population = NeatPopulation {
+            genomes: Vec::new(),
+            species: Vec::new(),
+            innovations: InnovationTracker::new(inputs, outputs),
+            next_species_id: 0,
+            generation: 0,
+            episode_seeds: Vec::new(),
+            rng_state: rng().random(),
+            config,
+        };
+        let neat = population.neat_config();
+        let mut rng = population.next_rng();
+        population.genomes = (0..population.config.training.population_size)
+            .map(|_| Genome::minimal(inputs, outputs, &neat, &mut rng, 0))
+            .collect();
+        population
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn config(&self) -> &ExperimentConfig {
+        &self.config
+    }
+
+    #[cfg(test)]
+    pub fn species(&self) -> &[Species] {
+        &self.species
+    }
+
+    fn neat_config(&self) -> NeatConfig {
+        match &self.config.genome {
+            GenomeMode::Neat(neat) => neat.clone(),
+            GenomeMode::Layers => NeatConfig::default(),
+        }
+    }
+
+    fn next_rng(&mut self) -> StdRng {
+        let mut rng = StdRng::seed_from_u64(self.rng_state);
+        self.rng_state = rng.random();
+        rng
+    }
+
+    /// Writes the whole population to `path` through a temporary file, like `Population`
+    pub fn save_checkpoint(&self, path: &str) -> std::io::Result<()> {
+        let json = serde_json::to_string(self)?;
+        let tmp_path = format!("{path}.tmp");
+        std::fs::write(&tmp_path, json)?;
+        std::fs::rename(&tmp_path, path)
+    }
+
+    /// Reads a checkpoint written by `save_checkpoint`, checking every genome on its own and
+    /// against the spaces of the game it plays
+    pub fn load_checkpoint(path: &str) -> std::io::Result<NeatPopulation> {
+        let json = std::fs::read_to_string(path)?;
+        let population: NeatPopulation = serde_json::from_str(&json)?;
+        for (idx, genome) in population.genomes.iter().enumerate() {
+            genome
+                .validate()
+                .and_then(|()| {
+                    config::check_fits_game(genome.count(NodeKind::Input), genome.count(NodeKind::Output), &population.config.game)
+                })
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("genome {idx}: {e}")))?;
+        }
+        Ok(population)
+    }
+
+    /// Episode seeds shared by every genome in this generation; empty in `Independent` mode
+    pub fn episode_seeds(&self) -> &[u64] {
+        &self.episode_seeds
+    }
+
+    pub fn best_genomes(&self, quantity: usize) -> Vec<Genome> {
+        let mut sorted = self.genomes.clone();
+        sorted.sort_by(|a, b| b.fitness.partial_cmp(&a.fitness).unwrap_or(std::cmp::Ordering::Equal));
+        sorted.into_iter().take(quantity).collect()
+    }
+
+    /// Plays the games of every genome and summarises the generation
+    pub fn update_fitness(&mut self) -> GenerationStats {
+        let start = Instant::now();
+        let (common_seeds, genome_seeds) = draw_episode_seeds(
+            self.config.training.evaluation,
+            self.generation,
+            self.config.training.iterations,
+            self.genomes.len(),
+            || self.next_rng(),
+        );
+        self.episode_seeds = common_seeds;
+
+        self.genomes
+            .par_iter_mut()
+            .zip(genome_seeds.par_iter())
+            .for_each(|(genome, seeds)| genome.iterate_to_update_fitness(seeds, &self.config.game));
+
+        let stats = GenerationStats::from_members(self.generation, &self.genomes, start.elapsed().as_secs_f64());
+        let hidden: usize = self.genomes.iter().map(|g| g.count(NodeKind::Hidden)).sum();
+        println!("[NEAT] {}", stats.summary());
+        println!(
+            "[NEAT] species: {}, avg hidden nodes: {:.2}",
+            self.species.len(),
+            hidden as f64 / self.genomes.len().max(1) as f64
+        );
+        stats
+    }
+
+    /// Groups the genomes into species, each then picks a random member to represent it next time
+    fn speciate(&mut self, config: &NeatConfig, rng: &mut impl Rng) {
+        for species in self.species.iter_mut() {
+            species.members.clear();
+        }
+        for (idx, genome) in self.genomes.iter().enumerate() {
+            match self
+                .species
+                .iter_mut()
+                .find(|s| genome.distance(&s.representative, config) < config.compatibility_threshold)
+            {
+                Some(species) => species.members.push(idx),
+                None => {
+                    self.species.push(Species {
+                        id: self.next_species_id,
+                        representative: genome.clone(),
+                        members: vec![idx],
+                        best_fitness: f64::NEG_INFINITY,
+                        stagnant_generations: 0,
+                    });
+                    self.next_species_id += 1;
+                }
+            }
+        }
+        self.species.retain(|s| !s.members.is_empty());
+        for species in self.species.iter_mut() {
+            let pick = species.members[rng.random_range(0..species.members.len())];
+            species.representative = self.genomes[pick].clone();
+        }
+    }
+
+    /// Drops species that stopped improving, always keeping the one holding the best genome
+    fn remove_stagnant_species(&mut self, config: &NeatConfig) {
+        let genomes = &self.genomes;
+        let best_of = |s: &Species| s.members.iter().map(|&i| genomes[i].fitness).fold(f64::NEG_INFINITY, f64::max);
+        for species in self.species.iter_mut() {
+            let best = best_of(species);
+            if best > species.best_fitness {
+                species.best_fitness = best;
+                species.stagnant_generations = 0;
+            } else {
+                species.stagnant_generations += 1;
+            }
+        }
+        let champion: Option<usize> = self
+            .species
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| best_of(a).partial_cmp(&best_of(b)).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(idx, _)| idx);
+        let mut idx = 0;
+        self.species.retain(|s| {
+            idx += 1;
+            Some(idx - 1) == champion || s.stagnant_generations <= config.stagnation_limit
+        });
+    }
+
+    /// Offspring per species, proportional to the mean fitness of its members (explicit fitness
+    /// sharing) and adding up to `size`
+    fn offspring_counts(&self, size: usize) -> Vec<usize> {
+        let shares: Vec<f64> = self
+            .species
+            .iter()
+            .map(|s| s.members.iter().map(|&i| self.genomes[i].fitness).sum::<f64>() / s.members.len() as f64)
+            .collect();
+        let total: f64 = shares.iter().sum();
+        let quotas: Vec<f64> = if total > 0.0 {
+            shares.iter().map(|share| share / total * size as f64).collect()
+        } else {
+            vec![size as f64 / self.species.len() as f64; self.species.len()]
+        };
+
+        let mut counts: Vec<usize> = quotas.iter().map(|q| q.floor() as usize).collect();
+        let mut by_remainder: Vec<usize> = (0..quotas.len()).collect();
+        by_remainder.sort_by(|&a, &b| (quotas[b] - quotas[b].floor()).partial_cmp(&(quotas[a] - quotas[a].floor())).unwrap());
+        for &idx in by_remainder.iter().cycle().take(size - counts.iter().sum::<usize>()) {
+            counts[idx] += 1;
+        }
+        counts
+    }
+
+    /// Replaces the genomes with the next generation: speciation, removal of stagnant species, then
+    /// each species keeps its champion and breeds its share of offspring from its best members
+    pub fn evolve(&mut self) {
+        let config = self.neat_config();
+        let mut rng = self.next_rng();
+        let size = self.config.training.population_size;
+        let next_generation = self.generation + 1;
+
+        self.speciate(&config, &mut rng);
+        self.remove_stagnant_species(&config);
+        let counts = self.offspring_counts(size);
+
+        let mut offspring: Vec<Genome> = Vec::with_capacity(size);
+        for (species, &count) in self.species.iter().zip(&counts) {
+            if count == 0 {
+                continue;
+            }
+            let mut ranked: Vec<&Genome> = species.members.iter().map(|&i| &self.genomes[i]).collect();
+            ranked.sort_by(|a, b| b.fitness.partial_cmp(&a.fitness).unwrap_or(std::cmp::Ordering::Equal));
+            let survivors = ranked.len().saturating_mul(config.survival_percentage).div_ceil(100).max(1);
+            let parents = &ranked[..survivors];
+
+            offspring.push(ranked[0].clone());
+            for _ in 1..count {
+                let first = parents[rng.random_range(0..parents.len())];
+                let mut child = if parents.len() > 1 && rng.random_range(0..100) < config.crossover_percentage {
+                    let second = parents[rng.random_range(0..parents.len())];
+                    let (fitter, other) = if first.fitness >= second.fitness { (first, second) } else { (second, first) };
+                    Genome::crossover(fitter, other, &config, &mut rng, next_generation)
+                } else {
+                    Genome { generation: next_generation, ..first.clone() }
+                };
+                child.mutate(&config, &mut self.innovations, &mut rng);
+                offspring.push(child);
+            }
+        }
+
+        self.genomes = offspring;
+        self.innovations.new_generation();
+        self.generation = next_generation;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TempPath;
+
+    fn seeded() -> StdRng {
+        StdRng::seed_from_u64(3)
+    }
+
+    fn linear_config() -> NeatConfig {
+        NeatConfig { hidden_activation: Activation::Linear, output_activation: Activation::Linear, ..NeatConfig::default() }
+    }
+
+    fn neat_experiment(population_size: usize) -> ExperimentConfig {
+        let mut config = ExperimentConfig::default();
+        config.training.population_size = population_size;
+        config.training.iterations = 2;
+        config.genome = GenomeMode::Neat(NeatConfig { add_node_percentage: 30, add_connection_percentage: 30, ..NeatConfig::default() });
+        config
+    }
+
+    #[test]
+    fn test_minimal_genome_is_a_linear_map() {
+        let mut genome = Genome::minimal(2, 1, &linear_config(), &mut seeded(), 0);
+        // Inputs 0 and 1, bias 2, output 3
+        for (c, w) in genome.connections.iter_mut().zip([2.0, -1.0, 0.5]) {
+            c.weight = w;
+        }
+        assert_eq!(genome.feedforward(&ndarray::array![[3.0], [4.0]]), ndarray::array![[2.5]]);
+        assert_eq!(genome.connections.iter().map(|c| c.innovation).collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_add_node_keeps_a_linear_network_unchanged() {
+        let mut genome = Genome::minimal(3, 2, &linear_config(), &mut seeded(), 0);
+        let mut tracker = InnovationTracker::new(3, 2);
+        let input = ndarray::array![[0.3], [-1.2], [2.0]];
+        let before = genome.feedforward(&input);
+
+        assert!(genome.add_node(&mut tracker, &mut seeded()));
+        assert_eq!(genome.nodes.iter().filter(|n| n.kind == NodeKind::Hidden).count(), 1);
+        assert_eq!(genome.connections.iter().filter(|c| !c.enabled).count(), 1);
+        let after = genome.feedforward(&input);
+        assert!((before - after).iter().all(|d| d.abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_same_split_in_a_generation_shares_innovations() {
+        let mut a = Genome::minimal(1, 1, &NeatConfig::default(), &mut seeded(), 0);
+        let mut b = a.clone();
+        let mut tracker = InnovationTracker::new(1, 1);
+        a.add_node(&mut tracker, &mut StdRng::seed_from_u64(1));
+        b.add_node(&mut tracker, &mut StdRng::seed_from_u64(1));
+        assert_eq!(a.nodes, b.nodes);
+        assert_eq!(
+            a.connections.iter().map(|c| c.innovation).collect::<Vec<_>>(),
+            b.connections.iter().map(|c| c.innovation).collect::<Vec<_>>()
+        );
+
+        // A later generation gets fresh numbers for the same change
+        tracker.new_generation();
+        let mut c = Genome::minimal(1, 1, &NeatConfig::default(), &mut seeded(), 0);
+        c.add_node(&mut tracker, &mut StdRng::seed_from_u64(1));
+        assert_ne!(c.nodes, a.nodes);
+    }
+
+    #[test]
+    fn test_structural_mutations_never_create_cycles() {
+        let mut genome = Genome::minimal(4, 3, &NeatConfig::default(), &mut seeded(), 0);
+        let mut tracker = InnovationTracker::new(4, 3);
+        let mut rng = seeded();
+        for _ in 0..200 {
+            genome.add_node(&mut tracker, &mut rng);
+            genome.add_connection(&mut tracker, &mut rng);
+        }
+        let computed = genome.nodes.iter().filter(|n| matches!(n.kind, NodeKind::Hidden | NodeKind::Output)).count();
+        assert_eq!(genome.evaluation_order().len(), computed);
+        assert!(genome.connections.windows(2).all(|w| w[0].innovation < w[1].innovation));
+        assert_eq!(genome.feedforward(&Array2::ones((4, 1))).nrows(), 3);
+    }
+
+    #[test]
+    fn test_crossover_takes_extra_genes_from_the_fitter_parent() {
+        let mut fitter = Genome::minimal(2, 1, &NeatConfig::default(), &mut seeded(), 0);
+        let mut other = fitter.clone();
+        let mut tracker = InnovationTracker::new(2, 1);
+        fitter.add_node(&mut tracker, &mut StdRng::seed_from_u64(5));
+        other.add_node(&mut tracker, &mut StdRng::seed_from_u64(6));
+        other.add_node(&mut tracker, &mut StdRng::seed_from_u64(7));
+
+        let child = Genome::crossover(&fitter, &other, &NeatConfig::default(), &mut seeded(), 1);
+        assert_eq!(child.nodes, fitter.nodes);
+        assert_eq!(
+            child.connections.iter().map(|c| c.innovation).collect::<Vec<_>>(),
+            fitter.connections.iter().map(|c| c.innovation).collect::<Vec<_>>()
+        );
+        assert_eq!(child.generation, 1);
+    }
+
+    #[test]
+    fn test_distance_grows_with_structural_differences() {
+        let config = NeatConfig::default();
+        let genome = Genome::minimal(3, 2, &config, &mut seeded(), 0);
+        assert_eq!(genome.distance(&genome, &config), 0.0);
+
+        let mut grown = genome.clone();
+        let mut tracker = InnovationTracker::new(3, 2);
+        grown.add_node(&mut tracker, &mut seeded());
+        // Two excess genes out of ten
+        assert!((genome.distance(&grown, &config) - config.excess_coefficient * 2.0 / 10.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_offspring_counts_add_up_to_the_population() {
+        let mut population = NeatPopulation::new(neat_experiment(10));
+        for (idx, genome) in population.genomes.iter_mut().enumerate() {
+            genome.fitness = idx as f64;
+            // Far apart weights, so the random initial ones cannot end up in a single species
+            genome.connections.iter_mut().for_each(|c| c.weight = idx as f64);
+        }
+        population.speciate(&NeatConfig { compatibility_threshold: 0.5, ..NeatConfig::default() }, &mut seeded());
+        assert!(population.species.len() > 1);
+        assert_eq!(population.offspring_counts(10).iter().sum::<usize>(), 10);
+        assert_eq!(population.offspring_counts(7).iter().sum::<usize>(), 7);
+    }
+
+    #[test]
+    fn test_evolution_keeps_the_size_and_grows_structure() {
+        let mut config = neat_experiment(12);
+        config.genome = GenomeMode::Neat(NeatConfig { add_node_percentage: 100, ..NeatConfig::default() });
+        let mut population = NeatPopulation::new(config);
+        population.rng_state = 11;
+        for _ in 0..5 {
+            let stats = population.update_fitness();
+            assert_eq!(stats.members, 12);
+            population.evolve();
+            assert_eq!(population.genomes.len(), 12);
+        }
+        assert_eq!(population.generation(), 5);
+        assert!(population.genomes.iter().any(|g| g.nodes.iter().any(|n| n.kind == NodeKind::Hidden)));
+        assert!(!population.species().is_empty());
+    }
+
+    #[test]
+    fn test_fitness_without_games_is_zero() {
+        let mut genome = Genome::minimal(7, 4, &NeatConfig::default(), &mut seeded(), 0);
+        genome.fitness = 5.0;
+        genome.iterate_to_update_fitness(&[], &GameConfig::default());
+        assert_eq!(genome.fitness, 0.0);
+    }
+
+    #[test]
+    fn test_checkpoint_roundtrip() {
+        let mut population = NeatPopulation::new(neat_experiment(6));
+        population.update_fitness();
+        population.evolve();
+        let file = TempPath::new("neat.json");
+        population.save_checkpoint(file.as_str()).unwrap();
+        let restored = NeatPopulation::load_checkpoint(file.as_str()).unwrap();
+        assert_eq!(restored.generation(), 1);
+        assert_eq!(restored.genomes.len(), 6);
+        assert_eq!(restored.species.len(), population.species.len());
+        assert_eq!(restored.innovations.next_innovation, population.innovations.next_innovation);
+    }
+
+    #[test]
+    fn test_checkpoint_with_broken_genomes_is_rejected() {
+        let file = TempPath::new("neat_broken.json");
+        let load_with = |break_genome: &dyn Fn(&mut Genome)| {
+            let mut population = NeatPopulation::new(neat_experiment(2));
+            break_genome(&mut population.genomes[1]);
+            population.save_checkpoint(file.as_str()).unwrap();
+            NeatPopulation::load_checkpoint(file.as_str()).map(|_| ()).map_err(|e| e.to_string())
+        };
+
+        assert!(load_with(&|_| {}).is_ok());
+        let err = load_with(&|g| g.connections[0].to = 99).unwrap_err();
+        assert!(err.contains("genome 1") && err.contains("node 99"), "{err}");
+        let err = load_with(&|g| {
+            // Output back into an input, disabled connections count since crossover may enable them
+            let output = g.connections[0].to;
+            g.connections.push(ConnectionGene { innovation: 100, from: output, to: 0, weight: 1.0, enabled: false });
+        })
+        .unwrap_err();
+        assert!(err.contains("cycle"), "{err}");
+        let err = load_with(&|g| {
+            g.nodes.retain(|n| n.id != 0);
+            g.connections.retain(|c| c.from != 0);
+        })
+        .unwrap_err();
+        assert!(err.contains("6 inputs") && err.contains("provides 7"), "{err}");
+        let err = load_with(&|g| g.nodes.push(NodeGene { id: 99, kind: NodeKind::Output })).unwrap_err();
+        assert!(err.contains("5 outputs") && err.contains("4 actions"), "{err}");
+        let err = load_with(&|g| g.nodes.swap(2, 3)).unwrap_err();
+        assert!(err.contains("sorted and unique"), "{err}");
+        let err = load_with(&|g| g.nodes[3].id = 2).unwrap_err();
+        assert!(err.contains("sorted and unique"), "{err}");
+        let err = load_with(&|g| {
+            // Same ids, but the bias now takes the place of the last input
+            g.nodes[6].kind = NodeKind::Bias;
+            g.nodes[7].kind = NodeKind::Input;
+        })
+        .unwrap_err();
+        assert!(err.contains("Input node 7 comes after the bias"), "{err}");
+        let err = load_with(&|g| g.connections.swap(0, 1)).unwrap_err();
+        assert!(err.contains("strictly increasing"), "{err}");
+        let err = load_with(&|g| g.connections[1].innovation = 0).unwrap_err();
+        assert!(err.contains("strictly increasing"), "{err}");
+
+        // A checkpoint with a bad board is an error, not a panic while building the game
+        let mut population = NeatPopulation::new(neat_experiment(2));
+        population.config.game.width = 1;
+        population.save_checkpoint(file.as_str()).unwrap();
+        let err = NeatPopulation::load_checkpoint(file.as_str()).err().unwrap().to_string();
+        assert!(err.contains("at least 2x2"), "{err}");
+    }
+
+    #[test]
+    fn test_neat_mode_is_read_from_the_experiment() {
+        let json = r#"{ "genome": { "Neat": { "add_node_percentage": 10 } } }"#;
+        let config: ExperimentConfig = serde_json::from_str(json).unwrap();
+        let GenomeMode::Neat(neat) = config.genome else {
+            panic!("expected NEAT");
+        };
+        assert_eq!(neat.add_node_percentage, 10);
+        assert_eq!(neat.compatibility_threshold, COMPATIBILITY_THRESHOLD);
+        assert!(NeatConfig { survival_percentage: 0, ..neat }.validate().is_err());
+    }
+}
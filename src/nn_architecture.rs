@@ -1,52 +1,574 @@
-use serde::Serialize;
+use ndarray::Array2;
+use rand::Rng;
+use rand_distr::{Distribution, Normal, Uniform};
+use serde::{Deserialize, Serialize};
 
-const INPUT_SIZE: usize = 7;
+// Layers of the default architecture, see `NN_Architecture::new`
+const INPUT_SIZE: usize = 7; // Size of the default feature observation
 const NEURONS_PER_LAYER_1: usize = 32;
 const NEURONS_PER_LAYER_2: usize = 64;
-const OUTPUT_SIZE: usize = 3;
+const OUTPUT_SIZE: usize = 4; // One per direction, the default action mode
 
 /// Enum representing activation functions
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Activation {
     Relu,
     Sigmoid,
+    Tanh,
+    /// ReLU letting through `alpha` times the negative values
+    LeakyRelu { alpha: f64 },
+    /// `alpha * (e^x - 1)` below zero, identity above
+    Elu { alpha: f64 },
+    /// Identity
+    Linear,
+    /// Normalises every column into probabilities summing to 1
+    Softmax,
 }
 
-/// Struct for a layer configuration
-#[derive(Debug, Clone, Serialize)]
+/// How the weights or biases of a new member are drawn. Fan-in and fan-out are the columns and rows
+/// of a layer's weight matrix, so a convolution counts the inputs of one filter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Initializer {
+    /// N(0, 1), what members have always used
+    #[default]
+    StandardNormal,
+    /// Glorot: uniform within ±sqrt(6 / (fan_in + fan_out))
+    XavierUniform,
+    /// Glorot: N(0, 2 / (fan_in + fan_out))
+    XavierNormal,
+    /// Kaiming: N(0, 2 / fan_in), suited to ReLU layers
+    He,
+    Uniform { low: f64, high: f64 },
+    Zeros,
+    /// Rows (or columns, whichever are fewer) form an orthonormal set
+    Orthogonal,
+}
+
+impl Initializer {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            Initializer::Uniform { low, high } if low >= high || !low.is_finite() || !high.is_finite() => {
+                Err(format!("Uniform initializer needs low < high, got {low} and {high}"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Matrix of `shape` for a layer with the given fans
+    pub fn sample(&self, shape: (usize, usize), fan_in: usize, fan_out: usize, rng: &mut impl Rng) -> Array2<f64> {
+        let fans = (fan_in + fan_out).max(1) as f64;
+        match *self {
+            Initializer::StandardNormal => normal_matrix(shape, 1.0, rng),
+            Initializer::XavierUniform => uniform_matrix(shape, -(6.0 / fans).sqrt(), (6.0 / fans).sqrt(), rng),
+            Initializer::XavierNormal => normal_matrix(shape, (2.0 / fans).sqrt(), rng),
+            Initializer::He => normal_matrix(shape, (2.0 / fan_in.max(1) as f64).sqrt(), rng),
+            Initializer::Uniform { low, high } => uniform_matrix(shape, low, high, rng),
+            Initializer::Zeros => Array2::zeros(shape),
+            Initializer::Orthogonal => orthogonal_matrix(shape, rng),
+        }
+    }
+}
+
+/// Initializers for the weights and the biases of a layer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Initialization {
+    pub weights: Initializer,
+    pub biases: Initializer,
+}
+
+fn normal_matrix(shape: (usize, usize), std_dev: f64, rng: &mut impl Rng) -> Array2<f64> {
+    let normal: Normal<f64> = Normal::new(0.0, std_dev).expect("Standard deviation is positive");
+    Array2::from_shape_fn(shape, |_| normal.sample(rng))
+}
+
+fn uniform_matrix(shape: (usize, usize), low: f64, high: f64, rng: &mut impl Rng) -> Array2<f64> {
+    let uniform: Uniform<f64> = Uniform::new(low, high).expect("Initializer was validated");
+    Array2::from_shape_fn(shape, |_| uniform.sample(rng))
+}
+
+/// Gram-Schmidt on the rows of a normal matrix, or on its columns when they are fewer
+fn orthogonal_matrix(shape: (usize, usize), rng: &mut impl Rng) -> Array2<f64> {
+    let (rows, cols) = shape;
+    if rows > cols {
+        return orthogonal_matrix((cols, rows), rng).reversed_axes();
+    }
+    let mut m: Array2<f64> = normal_matrix(shape, 1.0, rng);
+    for i in 0..rows {
+        for j in 0..i {
+            let projection = m.row(i).dot(&m.row(j));
+            let previous = m.row(j).to_owned();
+            m.row_mut(i).scaled_add(-projection, &previous);
+        }
+        let norm = m.row(i).dot(&m.row(i)).sqrt();
+        m.row_mut(i).mapv_inplace(|x| x / norm);
+    }
+    m
+}
+
+/// What a layer computes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LayerKind {
+    /// Fully connected, `output_dim` units each seeing the whole input
+    #[default]
+    Dense,
+    /// `channels` square filters of side `kernel` slid over a grid input
+    Conv2D { channels: usize, kernel: usize, stride: usize, padding: usize },
+    /// Elman layer of `output_dim` units that also sees its own output of the previous step, so
+    /// the agent can remember earlier observations within an episode
+    Recurrent,
+    /// Passes a grid on as a flat column for the following dense layers; has no parameters and
+    /// ignores its activation
+    Flatten,
+}
+
+/// Shape of the values flowing between layers. Grids travel flattened in channel, row, column order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Shape {
+    Flat(usize),
+    Grid { channels: usize, rows: usize, cols: usize },
+}
+
+impl Shape {
+    pub fn size(&self) -> usize {
+        match *self {
+            Shape::Flat(size) => size,
+            Shape::Grid { channels, rows, cols } => channels * rows * cols,
+        }
+    }
+}
+
+/// Struct for a layer configuration. For convolutional and flatten layers the dims are the
+/// flattened sizes, filled in by `NN_Architecture::infer_shapes`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LayerConfig {
+    #[serde(default)]
+    pub kind: LayerKind,
+    #[serde(default)]
     pub input_dim: usize,
+    #[serde(default)]
     pub output_dim: usize,
     pub activation: Activation,
+    /// Overrides the initialization of the architecture for this layer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub init: Option<Initialization>,
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct NN_Architecture {
     pub layers: Vec<LayerConfig>,
+    /// Shape of the observation when it is a grid; a flat column of `input_size` otherwise
+    #[serde(default)]
+    pub input_shape: Option<Shape>,
+    /// How new members draw their parameters, unless a layer sets its own
+    #[serde(default)]
+    pub init: Initialization,
 }
 
 impl NN_Architecture {
+    /// Two hidden layers between the feature observation and the actions, used when an
+    /// experiment does not describe its own network
     pub fn new() -> Self {
-        let nn_arch: Vec<LayerConfig> = vec![
-            LayerConfig {
-                input_dim: INPUT_SIZE,
-                output_dim: NEURONS_PER_LAYER_1,
-                activation: Activation::Relu,
-            },
-
-            LayerConfig {
-                input_dim: NEURONS_PER_LAYER_1,
-                output_dim: NEURONS_PER_LAYER_2,
-                activation: Activation::Relu,
-            },
-            LayerConfig {
-                input_dim: NEURONS_PER_LAYER_2,
-                output_dim: OUTPUT_SIZE,
-                activation: Activation::Sigmoid,
-            },
-        ];
-        NN_Architecture { layers: nn_arch }
+        NN_Architecture::builder(Shape::Flat(INPUT_SIZE))
+            .dense(NEURONS_PER_LAYER_1, Activation::Relu)
+            .dense(NEURONS_PER_LAYER_2, Activation::Relu)
+            .dense(OUTPUT_SIZE, Activation::Sigmoid)
+            .build()
+            .expect("Default architecture is valid")
+    }
+
+    /// Starts a network taking observations of `input_shape`, see `ArchitectureBuilder`
+    pub fn builder(input_shape: Shape) -> ArchitectureBuilder {
+        ArchitectureBuilder { input_shape, layers: Vec::new(), init: Initialization::default() }
+    }
+
+    /// Network of `layers` taking observations of `input_shape`. Only the output sizes of dense and
+    /// recurrent layers need to be given, every other dim is inferred and then validated.
+    pub fn from_layers(input_shape: Shape, layers: Vec<LayerConfig>) -> Result<Self, String> {
+        let mut architecture = NN_Architecture { layers, input_shape: None, init: Initialization::default() };
+        architecture.set_input_shape(input_shape);
+        architecture.infer_shapes()?;
+        architecture.validate()?;
+        Ok(architecture)
+    }
+
+    /// Checks that every layer consumes what the previous one produces
+    pub fn validate(&self) -> Result<(), String> {
+        if self.layers.is_empty() {
+            return Err("Architecture needs at least one layer".to_string());
+        }
+        for (idx, layer) in self.layers.iter().enumerate() {
+            if layer.input_dim == 0 || layer.output_dim == 0 {
+                return Err(format!("Layer {} has a zero dimension", idx));
+            }
+            if idx > 0 && self.layers[idx - 1].output_dim != layer.input_dim {
+                return Err(format!(
+                    "Layer {} expects {} inputs but layer {} outputs {}",
+                    idx, layer.input_dim, idx - 1, self.layers[idx - 1].output_dim
+                ));
+            }
+        }
+        for init in self.layers.iter().filter_map(|l| l.init).chain([self.init]) {
+            init.weights.validate()?;
+            init.biases.validate()?;
+        }
+        let shapes = self.shapes()?;
+        for (idx, layer) in self.layers.iter().enumerate() {
+            if layer.input_dim != shapes[idx].size() || layer.output_dim != shapes[idx + 1].size() {
+                return Err(format!(
+                    "Layer {} is {} -> {} but its shapes give {} -> {}",
+                    idx, layer.input_dim, layer.output_dim, shapes[idx].size(), shapes[idx + 1].size()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Input shape of every layer followed by the output shape of the network, derived from the
+    /// input shape and the layer kinds
+    pub fn shapes(&self) -> Result<Vec<Shape>, String> {
+        let mut shape: Shape = self.input_shape.unwrap_or(Shape::Flat(self.input_size()));
+        let mut shapes: Vec<Shape> = vec![shape];
+        for (idx, layer) in self.layers.iter().enumerate() {
+            shape = match (layer.kind, shape) {
+                (LayerKind::Dense | LayerKind::Recurrent, _) => Shape::Flat(layer.output_dim),
+                (LayerKind::Conv2D { channels, kernel, stride, padding }, Shape::Grid { rows, cols, .. }) => {
+                    if kernel == 0 || stride == 0 {
+                        return Err(format!("Layer {} needs a kernel and stride of at least 1", idx));
+                    }
+                    if rows + 2 * padding < kernel || cols + 2 * padding < kernel {
+                        return Err(format!("Layer {} has a kernel larger than its {}x{} input", idx, rows, cols));
+                    }
+                    Shape::Grid {
+                        channels,
+                        rows: (rows + 2 * padding - kernel) / stride + 1,
+                        cols: (cols + 2 * padding - kernel) / stride + 1,
+                    }
+                }
+                (LayerKind::Conv2D { .. }, Shape::Flat(_)) => {
+                    return Err(format!("Layer {} is convolutional but its input is not a grid", idx));
+                }
+                (LayerKind::Flatten, shape) => Shape::Flat(shape.size()),
+            };
+            shapes.push(shape);
+        }
+        Ok(shapes)
+    }
+
+    /// Fills in the dims of every layer from `shapes`, so only dense output sizes and the
+    /// convolution settings have to be written by hand
+    pub fn infer_shapes(&mut self) -> Result<(), String> {
+        let shapes = self.shapes()?;
+        for (idx, layer) in self.layers.iter_mut().enumerate() {
+            layer.input_dim = shapes[idx].size();
+            layer.output_dim = shapes[idx + 1].size();
+        }
+        Ok(())
+    }
+
+    /// Shapes of the weight and bias matrices of every layer. A convolution keeps one row of
+    /// `channels x kernel x kernel` weights per filter; a flatten layer has empty matrices. A
+    /// recurrent layer keeps its input weights followed by its recurrent weights in each row, so
+    /// crossover and mutation treat both alike.
+    pub fn parameter_shapes(&self) -> Vec<((usize, usize), (usize, usize))> {
+        let shapes = self.shapes().unwrap_or_default();
+        self.layers
+            .iter()
+            .enumerate()
+            .map(|(idx, layer)| match (layer.kind, shapes.get(idx)) {
+                (LayerKind::Conv2D { channels, kernel, .. }, Some(Shape::Grid { channels: input, .. })) => {
+                    ((channels, input * kernel * kernel), (channels, 1))
+                }
+                (LayerKind::Flatten, _) => ((0, 0), (0, 1)),
+                (LayerKind::Recurrent, _) => {
+                    ((layer.output_dim, layer.input_dim + layer.output_dim), (layer.output_dim, 1))
+                }
+                _ => ((layer.output_dim, layer.input_dim), (layer.output_dim, 1)),
+            })
+            .collect()
+    }
+
+    /// Initialization of every layer, its own or the one of the architecture
+    pub fn layer_initializations(&self) -> Vec<Initialization> {
+        self.layers.iter().map(|l| l.init.unwrap_or(self.init)).collect()
+    }
+
+    pub fn input_size(&self) -> usize {
+        match self.input_shape {
+            Some(shape) => shape.size(),
+            None => self.layers.first().map_or(0, |l| l.input_dim),
+        }
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.layers.last().map_or(0, |l| l.output_dim)
+    }
+
+    /// Makes the network take observations of `shape`, keeping grids for convolutional layers
+    pub fn set_input_shape(&mut self, shape: Shape) {
+        self.input_shape = match shape {
+            Shape::Grid { .. } => Some(shape),
+            Shape::Flat(_) => None,
+        };
+        self.set_input_size(shape.size());
+    }
+
+    /// Resizes the first layer to take the whole observation
+    pub fn set_input_size(&mut self, size: usize) {
+        if let Some(layer) = self.layers.first_mut() {
+            layer.input_dim = size;
+        }
+    }
+
+    /// Resizes the last layer so the network has one output per action
+    pub fn set_output_size(&mut self, size: usize) {
+        if let Some(layer) = self.layers.last_mut() {
+            layer.output_dim = size;
+        }
     }
 }
 
+impl Default for NN_Architecture {
+    fn default() -> Self {
+        NN_Architecture::new()
+    }
+}
+
+/// Adds layers one after the other, each taking the output of the previous one
+pub struct ArchitectureBuilder {
+    input_shape: Shape,
+    layers: Vec<LayerConfig>,
+    init: Initialization,
+}
+
+impl ArchitectureBuilder {
+    pub fn dense(self, units: usize, activation: Activation) -> Self {
+        self.layer(LayerKind::Dense, units, activation)
+    }
+
+    #[cfg(test)]
+    pub fn recurrent(self, units: usize, activation: Activation) -> Self {
+        self.layer(LayerKind::Recurrent, units, activation)
+    }
+
+    #[cfg(test)]
+    pub fn conv2d(self, channels: usize, kernel: usize, stride: usize, padding: usize, activation: Activation) -> Self {
+        self.layer(LayerKind::Conv2D { channels, kernel, stride, padding }, 0, activation)
+    }
+
+    #[cfg(test)]
+    pub fn flatten(self) -> Self {
+        self.layer(LayerKind::Flatten, 0, Activation::Linear)
+    }
+
+    /// Initialization of every layer without its own
+    #[cfg(test)]
+    pub fn init(mut self, weights: Initializer, biases: Initializer) -> Self {
+        self.init = Initialization { weights, biases };
+        self
+    }
+
+    /// Initialization of the last layer added
+    #[cfg(test)]
+    pub fn layer_init(mut self, weights: Initializer, biases: Initializer) -> Self {
+        if let Some(layer) = self.layers.last_mut() {
+            layer.init = Some(Initialization { weights, biases });
+        }
+        self
+    }
+
+    pub fn build(self) -> Result<NN_Architecture, String> {
+        let mut architecture = NN_Architecture::from_layers(self.input_shape, self.layers)?;
+        architecture.init = self.init;
+        architecture.validate()?;
+        Ok(architecture)
+    }
+
+    fn layer(mut self, kind: LayerKind, output_dim: usize, activation: Activation) -> Self {
+        self.layers.push(LayerConfig { kind, input_dim: 0, output_dim, activation, init: None });
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(kind: LayerKind, output_dim: usize) -> LayerConfig {
+        LayerConfig { kind, input_dim: 0, output_dim, activation: Activation::Relu, init: None }
+    }
+
+    fn conv_architecture() -> NN_Architecture {
+        NN_Architecture {
+            layers: vec![
+                layer(LayerKind::Conv2D { channels: 8, kernel: 3, stride: 1, padding: 1 }, 0),
+                layer(LayerKind::Conv2D { channels: 4, kernel: 3, stride: 2, padding: 0 }, 0),
+                layer(LayerKind::Flatten, 0),
+                layer(LayerKind::Dense, 3),
+            ],
+            input_shape: Some(Shape::Grid { channels: 4, rows: 8, cols: 10 }),
+            init: Initialization::default(),
+        }
+    }
+
+    #[test]
+    fn test_default_architecture_is_valid() {
+        assert!(NN_Architecture::new().validate().is_ok());
+        assert_eq!(NN_Architecture::new().input_size(), INPUT_SIZE);
+        assert_eq!(NN_Architecture::new().layers[1].input_dim, NEURONS_PER_LAYER_1);
+    }
+
+    #[test]
+    fn test_builder_matches_hand_written_layers() {
+        let built = NN_Architecture::builder(Shape::Grid { channels: 4, rows: 8, cols: 10 })
+            .conv2d(8, 3, 1, 1, Activation::Relu)
+            .conv2d(4, 3, 2, 0, Activation::Relu)
+            .flatten()
+            .dense(3, Activation::Relu)
+            .build()
+            .unwrap();
+        let mut expected = conv_architecture();
+        expected.infer_shapes().unwrap();
+
+        assert_eq!(built.shapes(), expected.shapes());
+        assert_eq!(built.parameter_shapes(), expected.parameter_shapes());
+    }
+
+    #[test]
+    fn test_from_layers_rejects_inconsistent_layers() {
+        let flat = NN_Architecture::from_layers(Shape::Flat(7), vec![layer(LayerKind::Dense, 0)]);
+        assert!(flat.unwrap_err().contains("zero dimension"));
+
+        let conv = NN_Architecture::builder(Shape::Flat(7)).conv2d(4, 3, 1, 0, Activation::Relu).build();
+        assert!(conv.unwrap_err().contains("not a grid"));
+
+        let sized = NN_Architecture::builder(Shape::Flat(5)).dense(4, Activation::Tanh).build().unwrap();
+        assert_eq!((sized.input_size(), sized.output_size()), (5, 4));
+    }
+
+    #[test]
+    fn test_shapes_are_inferred_through_convolutions() {
+        let mut arch = conv_architecture();
+        arch.infer_shapes().unwrap();
+
+        assert_eq!(
+            arch.shapes().unwrap(),
+            vec![
+                Shape::Grid { channels: 4, rows: 8, cols: 10 },
+                Shape::Grid { channels: 8, rows: 8, cols: 10 },
+                Shape::Grid { channels: 4, rows: 3, cols: 4 },
+                Shape::Flat(48),
+                Shape::Flat(3),
+            ]
+        );
+        assert_eq!(arch.layers[3].input_dim, 48);
+        assert_eq!(arch.input_size(), 320);
+        assert!(arch.validate().is_ok());
+        assert_eq!(arch.parameter_shapes()[0], ((8, 4 * 9), (8, 1)));
+        assert_eq!(arch.parameter_shapes()[2], ((0, 0), (0, 1)));
+    }
+
+    #[test]
+    fn test_convolution_needs_a_grid() {
+        let mut arch = conv_architecture();
+        arch.input_shape = None;
+        assert!(arch.infer_shapes().is_err());
+
+        let mut arch = conv_architecture();
+        arch.layers[0].kind = LayerKind::Conv2D { channels: 8, kernel: 12, stride: 1, padding: 0 };
+        assert!(arch.infer_shapes().is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_dims_not_matching_shapes() {
+        let mut arch = conv_architecture();
+        arch.infer_shapes().unwrap();
+        arch.layers[1].output_dim = 50;
+        arch.layers[2].input_dim = 50;
+        arch.layers[2].output_dim = 50;
+        arch.layers[3].input_dim = 50;
+        assert!(arch.validate().is_err());
+    }
+
+    #[test]
+    fn test_recurrent_weights_include_the_previous_output() {
+        let mut arch = NN_Architecture {
+            layers: vec![layer(LayerKind::Recurrent, 16), layer(LayerKind::Dense, 3)],
+            input_shape: None,
+            init: Initialization::default(),
+        };
+        arch.set_input_size(7);
+        arch.infer_shapes().unwrap();
+        assert!(arch.validate().is_ok());
+        assert_eq!(arch.parameter_shapes()[0], ((16, 7 + 16), (16, 1)));
+        assert_eq!(arch.parameter_shapes()[1], ((3, 16), (3, 1)));
+    }
+
+    #[test]
+    fn test_dense_layers_without_kind_still_load() {
+        let json = r#"{ "layers": [ { "input_dim": 7, "output_dim": 3, "activation": "Sigmoid" } ] }"#;
+        let arch: NN_Architecture = serde_json::from_str(json).unwrap();
+        assert_eq!(arch.layers[0].kind, LayerKind::Dense);
+        assert_eq!(arch.input_shape, None);
+        assert!(arch.validate().is_ok());
+    }
+
+    fn seeded() -> rand::rngs::StdRng {
+        rand::SeedableRng::seed_from_u64(11)
+    }
+
+    #[test]
+    fn test_xavier_uniform_stays_within_its_limit() {
+        let m = Initializer::XavierUniform.sample((64, 32), 32, 64, &mut seeded());
+        let limit = (6.0_f64 / 96.0).sqrt();
+        assert!(m.iter().all(|x| x.abs() <= limit));
+        assert!(m.iter().any(|x| x.abs() > limit / 2.0));
+    }
+
+    #[test]
+    fn test_he_scales_with_fan_in() {
+        let m = Initializer::He.sample((200, 50), 50, 200, &mut seeded());
+        let variance = m.iter().map(|x| x * x).sum::<f64>() / m.len() as f64;
+        assert!((variance - 2.0 / 50.0).abs() < 0.005, "{variance}");
+    }
+
+    #[test]
+    fn test_orthogonal_rows_and_columns() {
+        let wide = Initializer::Orthogonal.sample((3, 5), 5, 3, &mut seeded());
+        let tall = Initializer::Orthogonal.sample((5, 3), 3, 5, &mut seeded());
+        let identity: Array2<f64> = Array2::eye(3);
+        assert!((wide.dot(&wide.t()) - &identity).iter().all(|x| x.abs() < 1e-9));
+        assert!((tall.t().dot(&tall) - &identity).iter().all(|x| x.abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_zeros_and_uniform_range() {
+        assert!(Initializer::Zeros.sample((4, 1), 3, 4, &mut seeded()).iter().all(|&x| x == 0.0));
+        let m = Initializer::Uniform { low: 2.0, high: 3.0 }.sample((10, 10), 10, 10, &mut seeded());
+        assert!(m.iter().all(|x| (2.0..3.0).contains(x)));
+        assert!(Initializer::Uniform { low: 1.0, high: 1.0 }.validate().is_err());
+    }
+
+    #[test]
+    fn test_layers_override_the_architecture_initialization() {
+        let arch = NN_Architecture::builder(Shape::Flat(7))
+            .init(Initializer::He, Initializer::Zeros)
+            .dense(16, Activation::Relu)
+            .dense(3, Activation::Sigmoid)
+            .layer_init(Initializer::XavierNormal, Initializer::Zeros)
+            .build()
+            .unwrap();
+        let inits = arch.layer_initializations();
+        assert_eq!(inits[0], Initialization { weights: Initializer::He, biases: Initializer::Zeros });
+        assert_eq!(inits[1].weights, Initializer::XavierNormal);
+
+        let json = serde_json::to_string(&arch).unwrap();
+        assert_eq!(serde_json::from_str::<NN_Architecture>(&json).unwrap(), arch);
+        let bad = NN_Architecture::builder(Shape::Flat(7))
+            .dense(3, Activation::Relu)
+            .layer_init(Initializer::Uniform { low: 1.0, high: 0.0 }, Initializer::Zeros)
+            .build();
+        assert!(bad.is_err());
+    }
+}
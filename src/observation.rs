@@ -0,0 +1,319 @@
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::environment::Observation;
+use crate::point::Point;
+use crate::snakegame::{Direction, GRID_CHANNELS, GameConfig, GridOptions, RAY_COUNT, Snakegame};
+
+/// Number of values of the original sensors, see `Snakegame::get_current_input`
+pub const FEATURES_SIZE: usize = 7;
+/// Number of values of `CompassEncoder`
+pub const COMPASS_SIZE: usize = 9;
+
+/// Turns the state of a game into the column of values fed to the network
+pub trait ObservationEncoder {
+    /// Number of values produced for a game played with `config`
+    fn size(&self, config: &GameConfig) -> usize;
+
+    fn encode(&self, game: &Snakegame) -> Observation;
+}
+
+/// The 7 sensors the snake has always used: wall and body closeness in front, left and right,
+/// and the angle to the apple
+pub struct FeatureEncoder;
+
+impl ObservationEncoder for FeatureEncoder {
+    fn size(&self, _config: &GameConfig) -> usize {
+        FEATURES_SIZE
+    }
+
+    fn encode(&self, game: &Snakegame) -> Observation {
+        game.get_current_input()
+    }
+}
+
+/// The sensors read along the compass instead of the heading: body nearby to the north, east,
+/// south and west, position between opposite walls, offset to the apple on each axis and whether
+/// the apple lies just ahead
+pub struct CompassEncoder;
+
+impl ObservationEncoder for CompassEncoder {
+    fn size(&self, _config: &GameConfig) -> usize {
+        COMPASS_SIZE
+    }
+
+    fn encode(&self, game: &Snakegame) -> Observation {
+        let mut values: Vec<f64> = [Direction::North, Direction::East, Direction::South, Direction::West]
+            .into_iter()
+            .map(|d| game.have_snake_in_direction(d))
+            .collect();
+        values.extend([
+            game.distance_to_north_south_wall(),
+            game.distance_to_west_east_wall(),
+            game.get_fruit_north_south_distance(),
+            game.get_fruit_east_west_distance(),
+            game.distance_fruit_infront(),
+        ]);
+        column(values)
+    }
+}
+
+/// Wall, body and apple readings of the 8 rays of `Snakegame::ray_vision`
+pub struct RayEncoder;
+
+impl ObservationEncoder for RayEncoder {
+    fn size(&self, _config: &GameConfig) -> usize {
+        RAY_COUNT * 3
+    }
+
+    fn encode(&self, game: &Snakegame) -> Observation {
+        column(game.ray_vision().iter().flat_map(|r| [r.wall, r.body, r.apple]).collect())
+    }
+}
+
+/// One-hot body, head and apple planes of the whole board, row by row
+pub struct GridEncoder;
+
+impl ObservationEncoder for GridEncoder {
+    fn size(&self, config: &GameConfig) -> usize {
+        config.width * config.height * 3
+    }
+
+    fn encode(&self, game: &Snakegame) -> Observation {
+        let config = game.config();
+        let head = game.get_snake_head_pos();
+        let mut values: Vec<f64> = vec![0.0; self.size(config)];
+        let plane = config.width * config.height;
+        let cell = |p: &Point| p.y as usize * config.width + p.x as usize;
+
+        for p in game.snake() {
+            if *p != head {
+                values[cell(p)] = 1.0;
+            }
+        }
+        values[plane + cell(&head)] = 1.0;
+        values[2 * plane + cell(&game.apple_position())] = 1.0;
+        column(values)
+    }
+}
+
+/// `size` x `size` cells centred on the head, with planes for walls, body and apple
+pub struct WindowEncoder {
+    pub size: usize,
+}
+
+impl ObservationEncoder for WindowEncoder {
+    fn size(&self, _config: &GameConfig) -> usize {
+        self.size * self.size * 3
+    }
+
+    fn encode(&self, game: &Snakegame) -> Observation {
+        let config = game.config();
+        let head = game.get_snake_head_pos();
+        let radius = (self.size / 2) as isize;
+        let plane = self.size * self.size;
+        let mut values: Vec<f64> = vec![0.0; plane * 3];
+
+        for (row, dy) in (-radius..=radius).enumerate() {
+            for (col, dx) in (-radius..=radius).enumerate() {
+                let p = Point { x: head.x + dx, y: head.y + dy };
+                let idx = row * self.size + col;
+                if !config.contains(&p) {
+                    values[idx] = 1.0;
+                } else if game.snake().contains(&p) {
+                    values[plane + idx] = 1.0;
+                } else if game.apple_position() == p {
+                    values[2 * plane + idx] = 1.0;
+                }
+            }
+        }
+        column(values)
+    }
+}
+
+/// `Snakegame::grid_tensor` flattened channel by channel and row by row, for spatial layers that
+/// restore its `shape`
+pub struct TensorEncoder {
+    pub options: GridOptions,
+}
+
+impl TensorEncoder {
+    /// Channels, rows and columns of the grid
+    pub fn shape(&self, config: &GameConfig) -> (usize, usize, usize) {
+        let (rows, cols) = self.options.dims(config);
+        (GRID_CHANNELS, rows, cols)
+    }
+}
+
+impl ObservationEncoder for TensorEncoder {
+    fn size(&self, config: &GameConfig) -> usize {
+        let (channels, rows, cols) = self.shape(config);
+        channels * rows * cols
+    }
+
+    fn encode(&self, game: &Snakegame) -> Observation {
+        // A standard layout array iterates in channel, row, column order
+        column(game.grid_tensor(&self.options).into_iter().collect())
+    }
+}
+
+/// Encoder used by an experiment
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ObservationMode {
+    #[default]
+    Features,
+    /// Sensors in absolute directions, see `CompassEncoder`
+    Compass,
+    /// Ray-cast vision in 8 directions, relative to the heading
+    Rays,
+    Grid,
+    /// Square window of odd `size` around the head
+    Window { size: usize },
+    /// Multi-channel grid, see `TensorEncoder`
+    Tensor(GridOptions),
+}
+
+impl ObservationMode {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            ObservationMode::Window { size } if size % 2 == 0 => {
+                Err(format!("Observation window must have an odd size, got {size}"))
+            }
+            ObservationMode::Tensor(options) => options.validate(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl ObservationEncoder for ObservationMode {
+    fn size(&self, config: &GameConfig) -> usize {
+        match *self {
+            ObservationMode::Features => FeatureEncoder.size(config),
+            ObservationMode::Compass => CompassEncoder.size(config),
+            ObservationMode::Rays => RayEncoder.size(config),
+            ObservationMode::Grid => GridEncoder.size(config),
+            ObservationMode::Window { size } => WindowEncoder { size }.size(config),
+            ObservationMode::Tensor(options) => TensorEncoder { options }.size(config),
+        }
+    }
+
+    fn encode(&self, game: &Snakegame) -> Observation {
+        match *self {
+            ObservationMode::Features => FeatureEncoder.encode(game),
+            ObservationMode::Compass => CompassEncoder.encode(game),
+            ObservationMode::Rays => RayEncoder.encode(game),
+            ObservationMode::Grid => GridEncoder.encode(game),
+            ObservationMode::Window { size } => WindowEncoder { size }.encode(game),
+            ObservationMode::Tensor(options) => TensorEncoder { options }.encode(game),
+        }
+    }
+}
+
+fn column(values: Vec<f64>) -> Observation {
+    let len = values.len();
+    Array2::from_shape_vec((len, 1), values).expect("A vector always fits a column")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snakegame::Direction;
+
+    fn game(observation: ObservationMode) -> Snakegame {
+        let config = GameConfig {
+            width: 10,
+            height: 8,
+            initial_length: 3,
+            start: Point { x: 4, y: 4 },
+            heading: Direction::North,
+            observation,
+            ..GameConfig::default()
+        };
+        Snakegame::with_seed(config, 1)
+    }
+
+    #[test]
+    fn test_every_encoder_matches_its_size() {
+        let modes = [
+            ObservationMode::Features,
+            ObservationMode::Compass,
+            ObservationMode::Rays,
+            ObservationMode::Grid,
+            ObservationMode::Window { size: 5 },
+            ObservationMode::Tensor(GridOptions::default()),
+            ObservationMode::Tensor(GridOptions { window: Some(7), rotate: true }),
+        ];
+        for mode in modes {
+            let game = game(mode);
+            assert_eq!(mode.encode(&game).nrows(), mode.size(game.config()), "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn test_rays_see_body_and_wall() {
+        // Heading north from (4, 4) the body lies right behind the head, at (4, 5)
+        let values = RayEncoder.encode(&game(ObservationMode::Rays));
+        let south = 4 * 3;
+        assert_eq!(values[[south + 1, 0]], 1.0);
+        // Leaving the board north takes 5 steps on a 10 wide board
+        assert!((values[[0, 0]] - (1.0 - 4.0 / 10.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_compass_reads_absolute_directions() {
+        // Heading north from (4, 4) the body lies right behind the head, to the south
+        let game = game(ObservationMode::Compass);
+        let values = CompassEncoder.encode(&game);
+        assert_eq!(values.column(0).iter().take(4).copied().collect::<Vec<_>>(), vec![-1.0, -1.0, 1.0, -1.0]);
+        assert_eq!(values[[4, 0]], game.distance_to_north_south_wall());
+        assert_eq!(values[[8, 0]], game.distance_fruit_infront());
+    }
+
+    #[test]
+    fn test_grid_marks_head_body_and_apple_once() {
+        let game = game(ObservationMode::Grid);
+        let values = GridEncoder.encode(&game);
+        let plane = 10 * 8;
+        let sum = |from: usize| values.iter().skip(from).take(plane).sum::<f64>();
+        assert_eq!(sum(0), 2.0);
+        assert_eq!(sum(plane), 1.0);
+        assert_eq!(sum(2 * plane), 1.0);
+        assert_eq!(values[[plane + 4 * 10 + 4, 0]], 1.0);
+    }
+
+    #[test]
+    fn test_window_sees_walls_near_the_edge() {
+        let mut config = *game(ObservationMode::Features).config();
+        config.start = Point { x: 0, y: 4 };
+        config.heading = Direction::East;
+        config.initial_length = 1;
+        let game = Snakegame::with_seed(config, 1);
+        let values = WindowEncoder { size: 3 }.encode(&game);
+
+        // The left column of the window is outside the board
+        for row in 0..3 {
+            assert_eq!(values[[row * 3, 0]], 1.0);
+            assert_eq!(values[[row * 3 + 2, 0]], 0.0);
+        }
+        // The head itself is a body cell
+        assert_eq!(values[[9 + 4, 0]], 1.0);
+    }
+
+    #[test]
+    fn test_tensor_keeps_channel_row_column_order() {
+        let game = game(ObservationMode::Features);
+        let encoder = TensorEncoder { options: GridOptions::default() };
+        let values = encoder.encode(&game);
+        let grid = game.grid_tensor(&GridOptions::default());
+
+        assert_eq!(encoder.shape(game.config()), (GRID_CHANNELS, 8, 10));
+        let restored = values.into_shape_with_order(grid.raw_dim()).unwrap();
+        assert_eq!(restored, grid);
+    }
+
+    #[test]
+    fn test_window_size_must_be_odd() {
+        assert!(ObservationMode::Window { size: 4 }.validate().is_err());
+        assert!(ObservationMode::Window { size: 5 }.validate().is_ok());
+    }
+}
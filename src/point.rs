@@ -1,6 +1,7 @@
 use std::fmt;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Point {
     pub x: isize,
     pub y: isize,
@@ -45,11 +46,12 @@ mod tests {
     }
 
     #[test]
-    fn test_point_saturating_bounds() {
+    fn test_point_steps_off_origin() {
+        // Points are signed so a move off the board can be detected as a wall hit
         let p = Point { x: 0, y: 0 };
 
-        assert_eq!(p.north(), Point { x: 0, y: 0 });
-        assert_eq!(p.west(), Point { x: 0, y: 0 });
+        assert_eq!(p.north(), Point { x: 0, y: -1 });
+        assert_eq!(p.west(), Point { x: -1, y: 0 });
     }
 }
 
@@ -1,58 +1,130 @@
-const DEFAULT_ITERATIONS: usize = 10;
-    
+use crate::config::ExperimentConfig;
+use crate::crossover::{self, Crossover, CrossoverKind, Mix, WeightedCrossover};
 use crate::member::Member;
-use rand::{Rng,rng};
+use crate::mutation::{Adaptation, MutationConfig, SigmaSchedule};
+use crate::selection::{Selection, SelectionStrategy};
+use crate::stats::GenerationStats;
+use rand::{Rng, SeedableRng, rng, rngs::StdRng, seq::SliceRandom};
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// Genetic operators used to breed crossovers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeneticConfig {
+    /// Operators breeding the crossovers, each picked with probability proportional to its weight
+    pub crossover_operators: Vec<WeightedCrossover>,
+    /// Applied to every crossover once it is bred
+    pub mutation: MutationConfig,
+    /// How the parents of each crossover are picked among the best members
+    pub selection: Selection,
+}
 
-const MIX_TYPE_ALL_PERCENTAGE: usize = 30;
-const MIX_TYPE_HALF_PERCENTAGE: usize = 60;
-
-const MIX_WEIGHTS_PERCENTAGE: usize = 50;
-const MIX_BIASES_PERCENTAGE: usize = 50;
-
-const MIX_MUTATE_PERCENTAGE: usize = 1;
+impl Default for GeneticConfig {
+    fn default() -> Self {
+        GeneticConfig {
+            crossover_operators: vec![WeightedCrossover { operator: CrossoverKind::Mix(Mix::default()), weight: 1.0 }],
+            mutation: MutationConfig::default(),
+            selection: Selection::default(),
+        }
+    }
+}
 
-#[derive(Debug, PartialEq)]
-pub enum MixType {
-    All,
-    //Perc(usize), // por ejemplo, 50 para 50%
-    Percentage,
-    Single,
+impl GeneticConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        crossover::validate_operators(&self.crossover_operators)?;
+        self.mutation.validate()?;
+        self.selection.validate()
+    }
 }
 
-#[derive(Debug, PartialEq)]
-pub enum MixTarget {
-    Weights,
-    Biases,
-    Both,
-    Random,
+/// How the episodes played by each member are chosen
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum EvaluationMode {
+    /// Every member plays its own random episodes
+    Independent,
+    /// Every member plays the same episode seeds, derived from `base_seed` and rotated each generation
+    CommonSeeds { base_seed: u64 },
 }
 
-//#[derive(Debug, Clone)]
+/// A generation of members together with the run settings, serialised as a checkpoint
+#[derive(Serialize, Deserialize)]
 pub struct Population {
     members: Vec<Member>,
-    iterations: usize,
-    killed_by_wall: usize,
-    killed_by_myself: usize,
-    killed_by_hunger: usize,
-    apples_eaten: usize,
-    average_fitness: f64
+    config: ExperimentConfig,
+    generation: usize,
+    episode_seeds: Vec<u64>,
+    /// Seed of the next rng drawn by `next_rng`, so a resumed run continues the same random stream
+    rng_state: u64,
+    /// Factor of the 1/5th rule on the scheduled mutation sigma
+    #[serde(default = "default_sigma_scale")]
+    sigma_scale: f64,
+}
+
+fn default_sigma_scale() -> f64 {
+    1.0
 }
 
 impl Population {
 
-    pub fn new(size:usize, iterations:Option<usize>, generation: usize ) -> Self {
-        let members: Vec<Member> = (0..size).map(|_| Member::new(None, None, None, generation)).collect();
-
-        Population { 
-            members: members,
-            iterations: iterations.unwrap_or(DEFAULT_ITERATIONS),
-            killed_by_wall: 0,
-            killed_by_myself: 0,
-            killed_by_hunger: 0,
-            apples_eaten: 0,
-            average_fitness: 0.0,
+    pub fn new(size:usize, generation: usize, config: ExperimentConfig) -> Self {
+        let mut population = Population { 
+            members: Vec::with_capacity(size),
+            config,
+            generation,
+            episode_seeds: Vec::new(),
+            rng_state: rng().random(),
+            sigma_scale: default_sigma_scale(),
+        };
+        population.add_random_members(size, generation);
+        population
+    }
+
+    /// Empty population for the following generation, keeping the settings and rng state of this run
+    pub fn next_generation(&self) -> Population {
+        Population {
+            members: Vec::new(),
+            config: self.config.clone(),
+            generation: self.generation + 1,
+            episode_seeds: Vec::new(),
+            rng_state: self.rng_state,
+            sigma_scale: self.sigma_scale,
+        }
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn config(&self) -> &ExperimentConfig {
+        &self.config
+    }
+
+    fn next_rng(&mut self) -> StdRng {
+        let mut rng = StdRng::seed_from_u64(self.rng_state);
+        self.rng_state = rng.random();
+        rng
+    }
+
+    /// Writes the whole population to `path`, going through a temporary file so a kill mid-write
+    /// never leaves a truncated checkpoint behind
+    pub fn save_checkpoint(&self, path: &str) -> std::io::Result<()> {
+        let json = serde_json::to_string(self)?;
+        let tmp_path = format!("{path}.tmp");
+        std::fs::write(&tmp_path, json)?;
+        std::fs::rename(&tmp_path, path)
+    }
+
+    pub fn load_checkpoint(path: &str) -> std::io::Result<Population> {
+        let json = std::fs::read_to_string(path)?;
+        let population: Population = serde_json::from_str(&json)?;
+        for (idx, member) in population.members.iter().enumerate() {
+            member
+                .validate()
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("member {idx}: {e}")))?;
         }
+        Ok(population)
     }
 
     pub fn add_members(&mut self, members: Vec<Member>) {
@@ -60,8 +132,9 @@ impl Population {
     }
 
     pub fn add_random_members(&mut self, quantity: usize, generation: usize) {
+        let mut rng = self.next_rng();
         let new_members: Vec<Member> = (0..quantity)
-            .map(|_| Member::new(None, None, None, generation))
+            .map(|_| Member::new(self.config.architecture.clone(), Some(rng.random()), generation))
             .collect();
         self.members.extend(new_members);
     }
@@ -76,239 +149,120 @@ impl Population {
         sorted_members.into_iter().take(quantity).collect()
     }
 
-    pub fn select_proportional_by_fitness(members: &[Member]) -> Member {
-        let total_fitness: f64 = members.iter().map(|m| m.fitness).sum();
-
-        // Genera un número aleatorio entre 0 y total_fitness (exclusivo)
-        let mut random_fitness_wheel: f64 = rand::rng().random_range(0.0..total_fitness);
-
-        for member in members {
-            if random_fitness_wheel < member.fitness {
-                return member.clone(); // Asume que Member implementa Clone
-            }
-            random_fitness_wheel -= member.fitness;
-        }
-
-        panic!("No se pudo seleccionar un miembro proporcionalmente. Verifica los datos de entrada.");
-    }
-
     pub fn add_crossovers_members(&mut self, best_members:Vec<Member>, quantity: usize, generation: usize) {
-        let mut rng = rand::rng();
+        let mut rng = self.next_rng();
 
         let mut new_members: Vec<Member> = Vec::with_capacity(quantity);
+        let sigma: f64 = self.config.genetic.mutation.scheduled_sigma(generation, self.sigma_scale);
 
-        for _ in 0..quantity {
-            let roll: usize = rng.random_range(0..100);
-            let mix_type: MixType = 
-                if roll < MIX_TYPE_ALL_PERCENTAGE {
-                    MixType::All
-                } else if roll < MIX_TYPE_HALF_PERCENTAGE {
-                    MixType::Percentage
-                } else {
-                    MixType::Single
-                };
-            
-            let rollw: usize = rng.random_range(0..100);
-            let rollb: usize = rng.random_range(0..100);
-            let mix_target: MixTarget = 
-                if rollw < MIX_WEIGHTS_PERCENTAGE && rollb < MIX_BIASES_PERCENTAGE {
-                    MixTarget::Both
-                } else if rollw < MIX_WEIGHTS_PERCENTAGE{    
-                    MixTarget::Weights
-                }else if rollb < MIX_BIASES_PERCENTAGE {    
-                    MixTarget::Biases
-                } else {
-                    MixTarget::Random
-                };
-            
-            let mutate: bool = rng.random_bool(MIX_MUTATE_PERCENTAGE as f64 / 100.0);
-
-            // Selecciona dos miembros aleatorios
-            let mem1: Member = Self::select_proportional_by_fitness(&best_members);
-            let mem2: Member = Self::select_proportional_by_fitness(&best_members);
-
-            // Crea un nuevo miembro cruzando los dos seleccionados
-            let new_member = Population::cross_members(&mem1, &mem2, mix_type, mix_target, mutate, generation);
+        // Parents of every crossover, two by two
+        let fitness: Vec<f64> = best_members.iter().map(|m| m.fitness).collect();
+        let mut parents: Vec<usize> = self.config.genetic.selection.select(&fitness, 2 * quantity, &mut rng);
+        parents.shuffle(&mut rng);
+
+        let genetic: &GeneticConfig = &self.config.genetic;
+        for pair in parents.chunks_exact(2) {
+            let mem1: &Member = &best_members[pair[0]];
+            let mem2: &Member = &best_members[pair[1]];
+
+            let operator: CrossoverKind = crossover::pick_operator(&genetic.crossover_operators, &mut rng);
+            let mut new_member = operator.cross(mem1, mem2, generation, &mut rng);
+            genetic.mutation.mutate_offspring(&mut new_member, [mem1, mem2], sigma, &mut rng);
             new_members.push(new_member);
         }
 
         self.add_members(new_members);
     }
 
-    pub fn cross_members(
-        mem1: &Member,
-        mem2: &Member,
-        mix_type: MixType,
-        mix_target: MixTarget,
-        mutate: bool,
-        generation: usize
-    ) -> Member {
-        let mut rng = rng();
-
-        let mut new_mem = Member::new(Some(mem1.weights.clone()), Some(mem1.biases.clone()), None, generation);
-
-        // Determinar si se cambian pesos y/o biases
-        let (change_weights, change_biases) = match mix_target {
-            MixTarget::Weights => (true, false),
-            MixTarget::Biases => (false, true),
-            MixTarget::Both => (true, true),
-            MixTarget::Random => (rng.random_bool(0.5), rng.random_bool(0.5)),
-        };
-
-        match mix_type {
-            MixType::All | MixType::Percentage => {
-                let perc: usize = match mix_type {
-                    MixType::All => 100,
-                    MixType::Percentage => 50,
-                    MixType::Single => 50 //not reached
-                };
-
-                if change_weights {
-                    for i in 0..new_mem.weights.len() {
-                        for j in 0..new_mem.weights[i].nrows() {
-                            for k in 0..new_mem.weights[i].ncols() {
-                                if rng.random_range(1..=100) <= perc {
-                                    new_mem.weights[i][[j, k]] = mem2.weights[i][[j, k]];
-                                }
-                            }
-                        }
-                    }
-                }
-
-                if change_biases {
-                    for i in 0..new_mem.biases.len() {
-                        for j in 0..new_mem.biases[i].nrows() {
-                            for k in 0..new_mem.biases[i].ncols() {
-                                if rng.random_range(1..=100) <= perc {
-                                    new_mem.biases[i][[j, k]] = mem2.biases[i][[j, k]];
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-
-            MixType::Single => {
-                if change_weights {
-                    let i = rng.random_range(0..new_mem.weights.len());
-                    let j = rng.random_range(0..new_mem.weights[i].nrows());
-                    let k = rng.random_range(0..new_mem.weights[i].ncols());
-                    new_mem.weights[i][[j, k]] = mem2.weights[i][[j, k]];
-                }
-
-                if change_biases {
-                    let i = rng.random_range(0..new_mem.biases.len());
-                    let j = rng.random_range(0..new_mem.biases[i].nrows());
-                    let k = rng.random_range(0..new_mem.biases[i].ncols());
-                    new_mem.biases[i][[j, k]] = mem2.biases[i][[j, k]];
-                }
-            }
-        }
-
-        // Mutación
-        if mutate {
-            if change_weights {
-                let i = rng.random_range(0..new_mem.weights.len());
-                let j = rng.random_range(0..new_mem.weights[i].nrows());
-                let k = rng.random_range(0..new_mem.weights[i].ncols());
-                new_mem.weights[i][[j, k]] = rng.random_range(-1.0..1.0);
-            }
-
-            if change_biases {
-                let i = rng.random_range(0..new_mem.biases.len());
-                let j = rng.random_range(0..new_mem.biases[i].nrows());
-                let k = rng.random_range(0..new_mem.biases[i].ncols());
-                new_mem.biases[i][[j, k]] = rng.random_range(-1.0..1.0);
-            }
-        }
+    /// Episode seeds shared by every member in this generation; empty in `Independent` mode
+    pub fn episode_seeds(&self) -> &[u64] {
+        &self.episode_seeds
+    }
 
-        new_mem
+    /// Same seed set for every member, different from one generation to the next
+    fn common_episode_seeds(base_seed: u64, generation: usize, iterations: usize) -> Vec<u64> {
+        let mut rng = StdRng::seed_from_u64(base_seed.wrapping_add(generation as u64));
+        (0..iterations).map(|_| rng.random()).collect()
     }
 
-    pub fn update_fitness(&mut self) {
-        // reset stats
-        self.killed_by_wall = 0;
-        self.killed_by_myself = 0;
-        self.killed_by_hunger = 0;
-        self.apples_eaten = 0;
-        self.average_fitness = 0.0;
+    /// Plays the games of every member and summarises the generation
+    pub fn update_fitness(&mut self) -> GenerationStats {
+        let start = Instant::now();
+        let (common_seeds, member_seeds) = draw_episode_seeds(
+            self.config.training.evaluation,
+            self.generation,
+            self.config.training.iterations,
+            self.members.len(),
+            || self.next_rng(),
+        );
+        self.episode_seeds = common_seeds;
 
-        // Use par_iter_mut and collect intermediate results
-        let stats: Vec<(usize, usize, usize, usize, f64)> = self
-            .members
+        self.members
             .par_iter_mut() // PARALEL .par_iter_mut(), NOT PARALEL .iter_mut()
-            .map(|member| {
-                member.iterate_to_update_fitness(self.iterations);
-                (
-                    member.killed_by_wall,
-                    member.killed_by_myself,
-                    member.killed_by_hunger,
-                    member.apples_eaten,
-                    member.fitness, //average score through iterations
-                )
-            })
-            .collect();
+            .zip(member_seeds.par_iter())
+            .for_each(|(member, seeds)| member.iterate_to_update_fitness(seeds, &self.config.game));
 
         // Aggregate all stats after parallel work
-        let mut total_fitness = 0.0;
-        let mut max_fitness = 0.0;
-        for (wall, myself, hunger, apples, fitness) in stats {
-            self.killed_by_wall += wall;
-            self.killed_by_myself += myself;
-            self.killed_by_hunger += hunger;
-            self.apples_eaten += apples;
-            total_fitness += fitness;
-            if fitness > max_fitness {
-                max_fitness = fitness;
-            }
-        }
+        let stats = GenerationStats::from_members(
+            self.generation,
+            &self.members,
+            start.elapsed().as_secs_f64(),
+        );
 
-        self.average_fitness = total_fitness / self.members.len() as f64;
+        println!("[Population] {}", stats.summary());
 
-        println!(
-            "[Population] max(Fit): {:.0}, avg(Fit): {:.0}",
-            max_fitness,
-            self.average_fitness,
-        );
+        let mutation: &MutationConfig = &self.config.genetic.mutation;
+        self.sigma_scale = mutation.adapt(self.sigma_scale, &mut self.members);
+        match mutation.adaptation {
+            Adaptation::None if mutation.schedule == SigmaSchedule::Constant => {}
+            Adaptation::SelfAdaptive { .. } => {
+                let sigmas: Vec<f64> = self.members.iter().filter_map(|m| m.sigma).collect();
+                if !sigmas.is_empty() {
+                    println!("[Mutation] mean(sigma): {:.4}", sigmas.iter().sum::<f64>() / sigmas.len() as f64);
+                }
+            }
+            _ => println!("[Mutation] sigma: {:.4}", mutation.scheduled_sigma(self.generation + 1, self.sigma_scale)),
+        }
+        stats
     }
+}
 
-    /*
-    pub fn update_fitness(&mut self) {
-        // reset stats
-        self.killed_by_wall = 0;
-        self.killed_by_myself = 0;
-        self.killed_by_hunger = 0;
-        self.apples_eaten = 0;
-        self.average_fitness = 0.0;
-
-        let mut total_fitness: f64 = 0.0;
-
-        for member in self.members.iter_mut() {
-            member.iterate_to_update_fitness(self.iterations);
-            
-            self.killed_by_wall += member.killed_by_wall;
-            self.killed_by_myself += member.killed_by_myself;
-            self.killed_by_hunger += member.killed_by_hunger;
-            self.apples_eaten += member.apples_eaten;
-            total_fitness += member.fitness;
+/// Seeds shared by every member in `CommonSeeds` mode (empty otherwise) and the seeds of the
+/// `iterations` games each of `count` members plays in `generation`. `rng` is only drawn from for
+/// independent episodes.
+pub fn draw_episode_seeds(
+    evaluation: EvaluationMode,
+    generation: usize,
+    iterations: usize,
+    count: usize,
+    rng: impl FnOnce() -> StdRng,
+) -> (Vec<u64>, Vec<Vec<u64>>) {
+    match evaluation {
+        EvaluationMode::Independent => {
+            let mut rng = rng();
+            let member_seeds = (0..count).map(|_| (0..iterations).map(|_| rng.random()).collect()).collect();
+            (Vec::new(), member_seeds)
         }
-        self.average_fitness = total_fitness / self.members.len() as f64;
-        
-        println!("[Population] avg(Fitness): {:.3}, K by wall: {}, K by myself: {}, K by hunger: {}, Apples eaten: {}",
-            self.average_fitness, 
-            self.killed_by_wall, 
-            self.killed_by_myself, 
-            self.killed_by_hunger, 
-            self.apples_eaten);
-    }
-    */
+        EvaluationMode::CommonSeeds { base_seed } => {
+            let seeds = Population::common_episode_seeds(base_seed, generation, iterations);
+            (seeds.clone(), vec![seeds; count])
+        }
+    }
 }
-    
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::member::Member;
-    //use ndarray::Array2;
+    use crate::config::{TempPath, DEFAULT_ITERATIONS};
+    use crate::nn_architecture::NN_Architecture;
+
+    fn config(iterations: Option<usize>, evaluation: Option<EvaluationMode>) -> ExperimentConfig {
+        let mut config = ExperimentConfig::default();
+        config.training.iterations = iterations.unwrap_or(DEFAULT_ITERATIONS);
+        config.training.evaluation = evaluation.unwrap_or(EvaluationMode::Independent);
+        config
+    }
 
     fn generate_random_u8_32() -> [u8; 32] {
         let mut rng = rand::rng();
@@ -319,25 +273,25 @@ mod tests {
 
     #[test]
     fn test_population_new_with_default_iterations() {
-        let pop = Population::new(5, None, 0);
+        let pop = Population::new(5, 0, config(None, None));
         assert_eq!(pop.members.len(), 5);
-        assert_eq!(pop.iterations, DEFAULT_ITERATIONS);
+        assert_eq!(pop.config.training.iterations, DEFAULT_ITERATIONS);
     }
 
     #[test]
     fn test_population_new_with_custom_iterations() {
-        let pop = Population::new(3, Some(42), 0);
+        let pop = Population::new(3, 0, config(Some(42), None));
         assert_eq!(pop.members.len(), 3);
-        assert_eq!(pop.iterations, 42);
+        assert_eq!(pop.config.training.iterations, 42);
     }
 
     #[test]
     fn test_add_members() {
-        let mut pop = Population::new(2, None, 0);
+        let mut pop = Population::new(2, 0, config(None, None));
 
         let extra_members = vec![
-            Member::new(None, None, Some(generate_random_u8_32()), 0),
-            Member::new(None, None, Some(generate_random_u8_32()), 0),
+            Member::new(NN_Architecture::new(), Some(generate_random_u8_32()), 0),
+            Member::new(NN_Architecture::new(), Some(generate_random_u8_32()), 0),
         ];
 
         pop.add_members(extra_members);
@@ -346,22 +300,22 @@ mod tests {
 
     #[test]
     fn test_add_random_members() {
-        let mut pop = Population::new(1, None, 0);
+        let mut pop = Population::new(1, 0, config(None, None));
         pop.add_random_members(3, 0);
         assert_eq!(pop.members.len(), 4);
     }
 
     #[test]
     fn test_best_members_sorted_by_fitness() {
-        let mut pop = Population::new(0, None, 0);
+        let mut pop = Population::new(0, 0, config(None, None));
 
-        let mut m1 = Member::new(None, None, Some(generate_random_u8_32()), 0);
+        let mut m1 = Member::new(NN_Architecture::new(), Some(generate_random_u8_32()), 0);
         m1.fitness = 10.0;
 
-        let mut m2 = Member::new(None, None, Some(generate_random_u8_32()), 0);
+        let mut m2 = Member::new(NN_Architecture::new(), Some(generate_random_u8_32()), 0);
         m2.fitness = 50.0;
 
-        let mut m3 = Member::new(None, None, Some(generate_random_u8_32()), 0);
+        let mut m3 = Member::new(NN_Architecture::new(), Some(generate_random_u8_32()), 0);
         m3.fitness = 30.0;
 
         pop.add_members(vec![m1, m2, m3]);
@@ -374,10 +328,10 @@ mod tests {
 
     #[test]
     fn test_best_members_limited_by_quantity() {
-        let mut pop = Population::new(0, None, 0);
+        let mut pop = Population::new(0, 0, config(None, None));
 
         for i in 0..10 {
-            let mut m = Member::new(None, None, Some(generate_random_u8_32()), 0);
+            let mut m = Member::new(NN_Architecture::new(), Some(generate_random_u8_32()), 0);
             m.fitness = i as f64;
             pop.members.push(m);
         }
@@ -389,44 +343,145 @@ mod tests {
     }
 
     fn generate_dummy_member(seed: [u8; 32]) -> Member {
-        Member::new(None, None, Some(seed), 0)
+        Member::new(NN_Architecture::new(), Some(seed), 0)
     }
 
     #[test]
-    fn test_cross_all_weights() {
-        let mem1 = generate_dummy_member([1; 32]);
-        let mem2 = generate_dummy_member([2; 32]);
+    fn test_common_seeds_shared_by_members() {
+        let mode = EvaluationMode::CommonSeeds { base_seed: 11 };
+        let mut pop = Population::new(0, 3, config(Some(4), Some(mode)));
+        let member = generate_dummy_member([8; 32]);
+        pop.add_members(vec![member.clone(), member]);
+
+        pop.update_fitness();
+
+        assert_eq!(pop.episode_seeds().len(), 4);
+        // Identical members playing identical episodes must score identically
+        assert_eq!(pop.members[0].fitness, pop.members[1].fitness);
+        assert_eq!(pop.members[0].apples_eaten, pop.members[1].apples_eaten);
+    }
 
-        let child = Population::cross_members(&mem1, &mem2, MixType::All, MixTarget::Weights, false, 0);
+    #[test]
+    fn test_common_seeds_rotate_each_generation() {
+        let gen1 = Population::common_episode_seeds(11, 1, 5);
+        let gen1_again = Population::common_episode_seeds(11, 1, 5);
+        let gen2 = Population::common_episode_seeds(11, 2, 5);
 
-        // Should be mostly equal to mem2 in weights, and equal to mem1 in biases
-        assert_ne!(child.weights, mem1.weights);
-        assert_eq!(child.biases, mem1.biases);
+        assert_eq!(gen1, gen1_again);
+        assert_ne!(gen1, gen2);
     }
 
     #[test]
-    fn test_cross_single_biases() {
-        let mem1 = generate_dummy_member([3; 32]);
-        let mem2 = generate_dummy_member([4; 32]);
+    fn test_independent_mode_records_no_seeds() {
+        let mut pop = Population::new(2, 0, config(Some(2), None));
+        pop.update_fitness();
+        assert!(pop.episode_seeds().is_empty());
+    }
 
-        let child = Population::cross_members(&mem1, &mem2, MixType::Single, MixTarget::Biases, false, 0);
+    #[test]
+    fn test_next_generation_keeps_run_settings() {
+        let mode = EvaluationMode::CommonSeeds { base_seed: 5 };
+        let mut pop = Population::new(3, 4, config(Some(7), Some(mode)));
+        pop.add_random_members(1, 4);
+
+        let next = pop.next_generation();
+        assert_eq!(next.generation(), 5);
+        assert_eq!(next.config, pop.config);
+        assert_eq!(next.rng_state, pop.rng_state);
+        assert!(next.members.is_empty());
+    }
 
-        assert_eq!(child.weights, mem1.weights); // weights unchanged
-        assert_ne!(child.biases, mem1.biases); // at least one bias changed
+    #[test]
+    fn test_checkpoint_roundtrip() {
+        let mode = EvaluationMode::CommonSeeds { base_seed: 21 };
+        let mut pop = Population::new(3, 9, config(Some(2), Some(mode)));
+        pop.members[1].fitness = 123.0;
+
+        let file = TempPath::new("checkpoint.json");
+        pop.save_checkpoint(file.as_str()).unwrap();
+        let mut restored = Population::load_checkpoint(file.as_str()).unwrap();
+
+        assert_eq!(restored.generation(), 9);
+        assert_eq!(restored.config, pop.config);
+        assert_eq!(restored.members.len(), 3);
+        assert_eq!(restored.members[1].fitness, 123.0);
+        assert_eq!(restored.members[0].weights, pop.members[0].weights);
+        assert_eq!(restored.members[2].biases, pop.members[2].biases);
+
+        // The random stream continues exactly where the saved run left off
+        restored.add_random_members(1, 9);
+        pop.add_random_members(1, 9);
+        assert_eq!(restored.members[3].weights, pop.members[3].weights);
     }
 
     #[test]
-    fn test_cross_both_with_mutation() {
-        let mem1 = generate_dummy_member([5; 32]);
-        let mem2 = generate_dummy_member([6; 32]);
+    fn test_crossovers_follow_the_rng_state() {
+        let parents: Vec<Member> = (0..3).map(|i| generate_dummy_member([i; 32])).collect();
+        let breed = || {
+            let mut pop = Population::new(0, 0, config(None, None));
+            pop.rng_state = 21;
+            pop.add_crossovers_members(parents.clone(), 10, 1);
+            pop.members
+        };
+        let (first, second) = (breed(), breed());
+        for (a, b) in first.iter().zip(&second) {
+            assert_eq!(a.weights, b.weights);
+            assert_eq!(a.biases, b.biases);
+        }
+    }
 
-        let child = Population::cross_members(&mem1, &mem2, MixType::All, MixTarget::Both, true, 0);
+    #[test]
+    fn test_crossovers_bred_from_parents_without_fitness() {
+        for selection in [Selection::Roulette, Selection::StochasticUniversal, Selection::Tournament { size: 2 }] {
+            let mut config = config(None, None);
+            config.genetic.selection = selection;
+            let mut pop = Population::new(0, 0, config);
+            let parents: Vec<Member> = (0..3).map(|i| generate_dummy_member([i; 32])).collect();
+
+            pop.add_crossovers_members(parents, 5, 1);
+            assert_eq!(pop.members.len(), 5);
+        }
+    }
 
-        assert_ne!(child.weights, mem1.weights);
-        assert_ne!(child.biases, mem1.biases);
+    #[test]
+    fn test_crossovers_inherit_mutation_state() {
+        let mut config = config(None, None);
+        config.genetic.mutation.adaptation = Adaptation::SelfAdaptive { tau: 0.2, min_sigma: 0.0 };
+        let mut pop = Population::new(0, 0, config);
+        let mut parents: Vec<Member> = (0..2).map(|i| generate_dummy_member([i; 32])).collect();
+        parents[0].fitness = 3.0;
+        parents[1].fitness = 5.0;
+
+        pop.add_crossovers_members(parents, 4, 1);
+        assert!(pop.members.iter().all(|m| m.sigma.is_some() && matches!(m.parent_fitness, Some(3.0 | 5.0))));
+    }
 
-        assert_ne!(child.weights, mem2.weights);
-        assert_ne!(child.biases, mem2.biases);
+    #[test]
+    fn test_every_crossover_operator_breeds_valid_members() {
+        let json = r#"[
+            { "operator": { "Mix": {} }, "weight": 1 }, { "operator": "Uniform", "weight": 1 },
+            { "operator": "OnePoint", "weight": 1 }, { "operator": "TwoPoint", "weight": 1 },
+            { "operator": "LayerSwap", "weight": 1 }, { "operator": "NeuronSwap", "weight": 1 },
+            { "operator": "AlignedNeuronSwap", "weight": 1 },
+            { "operator": { "Blend": { "alpha": 0.5 } }, "weight": 1 },
+            { "operator": { "SimulatedBinary": { "eta": 2 } }, "weight": 1 }
+        ]"#;
+        let mut config = config(None, None);
+        config.genetic.crossover_operators = serde_json::from_str(json).unwrap();
+        assert!(config.genetic.validate().is_ok());
+        let mut pop = Population::new(0, 0, config);
+        let parents: Vec<Member> = (0..3).map(|i| generate_dummy_member([i; 32])).collect();
+
+        pop.add_crossovers_members(parents, 40, 1);
+        assert_eq!(pop.members.len(), 40);
+        assert!(pop.members.iter().all(|m| m.validate().is_ok() && m.generation == 1));
     }
 
+    #[test]
+    fn test_selection_read_from_config() {
+        let genetic: GeneticConfig = serde_json::from_str(r#"{ "selection": { "Tournament": { "size": 4 } } }"#).unwrap();
+        assert_eq!(genetic.selection, Selection::Tournament { size: 4 });
+        assert_eq!(genetic.mutation, MutationConfig::default());
+        assert!(GeneticConfig { selection: Selection::Tournament { size: 0 }, ..genetic }.validate().is_err());
+    }
 }
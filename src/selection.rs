@@ -0,0 +1,256 @@
+use rand::Rng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+/// Picks the parents of the next crossovers from the fitness of the candidates
+pub trait SelectionStrategy {
+    /// Indices into `fitness` of `count` parents, in random order and possibly repeated
+    fn select(&self, fitness: &[f64], count: usize, rng: &mut StdRng) -> Vec<usize>;
+}
+
+/// Fitness proportionate: each spin of the wheel picks a candidate with probability
+/// fitness / total. Without any positive fitness every candidate is equally likely.
+pub struct Roulette;
+
+impl SelectionStrategy for Roulette {
+    fn select(&self, fitness: &[f64], count: usize, rng: &mut StdRng) -> Vec<usize> {
+        let weights = wheel_weights(fitness);
+        (0..count).map(|_| spin(&weights, rng.random_range(0.0..1.0))).collect()
+    }
+}
+
+/// Best of `size` candidates drawn at random, so only the order of the fitness matters
+pub struct Tournament {
+    pub size: usize,
+}
+
+impl SelectionStrategy for Tournament {
+    fn select(&self, fitness: &[f64], count: usize, rng: &mut StdRng) -> Vec<usize> {
+        (0..count)
+            .map(|_| {
+                (0..self.size)
+                    .map(|_| rng.random_range(0..fitness.len()))
+                    .max_by(|&a, &b| fitness[a].partial_cmp(&fitness[b]).unwrap_or(std::cmp::Ordering::Equal))
+                    .expect("Tournaments have at least one entrant")
+            })
+            .collect()
+    }
+}
+
+/// Roulette over ranks instead of fitness. The best of n candidates weighs `pressure` and the worst
+/// `2 - pressure`, linearly in between.
+pub struct LinearRank {
+    pub pressure: f64,
+}
+
+impl SelectionStrategy for LinearRank {
+    fn select(&self, fitness: &[f64], count: usize, rng: &mut StdRng) -> Vec<usize> {
+        let n = fitness.len();
+        let weights = rank_weights(fitness, |rank| {
+            if n == 1 {
+                1.0
+            } else {
+                2.0 - self.pressure + 2.0 * (self.pressure - 1.0) * rank as f64 / (n - 1) as f64
+            }
+        });
+        Roulette.select(&weights, count, rng)
+    }
+}
+
+/// Roulette over ranks where each step down the ranking multiplies the weight by `base`
+pub struct ExponentialRank {
+    pub base: f64,
+}
+
+impl SelectionStrategy for ExponentialRank {
+    fn select(&self, fitness: &[f64], count: usize, rng: &mut StdRng) -> Vec<usize> {
+        let n = fitness.len();
+        let weights = rank_weights(fitness, |rank| self.base.powi((n - 1 - rank) as i32));
+        Roulette.select(&weights, count, rng)
+    }
+}
+
+/// Uniform among the best `percentage` percent of the candidates
+pub struct Truncation {
+    pub percentage: usize,
+}
+
+impl SelectionStrategy for Truncation {
+    fn select(&self, fitness: &[f64], count: usize, rng: &mut StdRng) -> Vec<usize> {
+        let ranked = ranking(fitness);
+        let kept = (fitness.len() * self.percentage).div_ceil(100).max(1);
+        let best = &ranked[ranked.len() - kept..];
+        (0..count).map(|_| best[rng.random_range(0..best.len())]).collect()
+    }
+}
+
+/// One spin of a wheel with `count` evenly spaced pointers: the same expected picks as roulette,
+/// but every candidate gets within one pick of its share
+pub struct StochasticUniversal;
+
+impl SelectionStrategy for StochasticUniversal {
+    fn select(&self, fitness: &[f64], count: usize, rng: &mut StdRng) -> Vec<usize> {
+        if count == 0 {
+            return Vec::new();
+        }
+        let weights = wheel_weights(fitness);
+        let step = 1.0 / count as f64;
+        let start: f64 = rng.random_range(0.0..step);
+        let mut picks: Vec<usize> = (0..count).map(|i| spin(&weights, start + i as f64 * step)).collect();
+        picks.shuffle(rng);
+        picks
+    }
+}
+
+/// Selection strategy of an experiment
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Selection {
+    #[default]
+    Roulette,
+    Tournament { size: usize },
+    /// `pressure` between 1 (uniform) and 2
+    LinearRank { pressure: f64 },
+    /// `base` between 0 and 1, smaller is greedier
+    ExponentialRank { base: f64 },
+    Truncation { percentage: usize },
+    StochasticUniversal,
+}
+
+impl Selection {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            Selection::Tournament { size: 0 } => Err("Tournament size must be at least 1".to_string()),
+            Selection::LinearRank { pressure } if !(1.0..=2.0).contains(&pressure) => {
+                Err(format!("Linear rank pressure must be between 1 and 2, got {pressure}"))
+            }
+            Selection::ExponentialRank { base } if base <= 0.0 || base >= 1.0 => {
+                Err(format!("Exponential rank base must be between 0 and 1, got {base}"))
+            }
+            Selection::Truncation { percentage } if percentage == 0 || percentage > 100 => {
+                Err(format!("Truncation must keep between 1 and 100 percent, got {percentage}"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl SelectionStrategy for Selection {
+    fn select(&self, fitness: &[f64], count: usize, rng: &mut StdRng) -> Vec<usize> {
+        if fitness.is_empty() {
+            return Vec::new();
+        }
+        match *self {
+            Selection::Roulette => Roulette.select(fitness, count, rng),
+            Selection::Tournament { size } => Tournament { size }.select(fitness, count, rng),
+            Selection::LinearRank { pressure } => LinearRank { pressure }.select(fitness, count, rng),
+            Selection::ExponentialRank { base } => ExponentialRank { base }.select(fitness, count, rng),
+            Selection::Truncation { percentage } => Truncation { percentage }.select(fitness, count, rng),
+            Selection::StochasticUniversal => StochasticUniversal.select(fitness, count, rng),
+        }
+    }
+}
+
+/// Cumulative shares of the wheel. Negative fitness counts as zero and a wheel without any
+/// positive fitness is split evenly, instead of sampling from an empty range.
+fn wheel_weights(fitness: &[f64]) -> Vec<f64> {
+    let clamped: Vec<f64> = fitness.iter().map(|f| if f.is_finite() { f.max(0.0) } else { 0.0 }).collect();
+    let total: f64 = clamped.iter().sum();
+    let mut cumulative = 0.0;
+    clamped
+        .iter()
+        .map(|f| {
+            cumulative += if total > 0.0 { f / total } else { 1.0 / fitness.len() as f64 };
+            cumulative
+        })
+        .collect()
+}
+
+/// Candidate under `point`, a value in [0, 1)
+fn spin(cumulative: &[f64], point: f64) -> usize {
+    cumulative.partition_point(|&c| c <= point).min(cumulative.len() - 1)
+}
+
+/// Indices from the worst to the best fitness
+fn ranking(fitness: &[f64]) -> Vec<usize> {
+    let mut ranked: Vec<usize> = (0..fitness.len()).collect();
+    ranked.sort_by(|&a, &b| fitness[a].partial_cmp(&fitness[b]).unwrap_or(std::cmp::Ordering::Equal));
+    ranked
+}
+
+/// Weight of every candidate given the weight of each rank, 0 being the worst
+fn rank_weights(fitness: &[f64], weight_of_rank: impl Fn(usize) -> f64) -> Vec<f64> {
+    let mut weights = vec![0.0; fitness.len()];
+    for (rank, idx) in ranking(fitness).into_iter().enumerate() {
+        weights[idx] = weight_of_rank(rank);
+    }
+    weights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    const FITNESS: [f64; 4] = [10.0, 0.0, 30.0, 60.0];
+
+    fn counts(selection: Selection, fitness: &[f64], draws: usize) -> Vec<usize> {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut counts = vec![0; fitness.len()];
+        for idx in selection.select(fitness, draws, &mut rng) {
+            counts[idx] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn test_roulette_follows_fitness_and_survives_zero_total() {
+        let picks = counts(Selection::Roulette, &FITNESS, 10_000);
+        assert_eq!(picks[1], 0);
+        assert!((picks[3] as f64 / 10_000.0 - 0.6).abs() < 0.03, "{picks:?}");
+
+        let picks = counts(Selection::Roulette, &[0.0, 0.0, 0.0], 3_000);
+        assert!(picks.iter().all(|&c| c > 900), "{picks:?}");
+    }
+
+    #[test]
+    fn test_tournament_ignores_fitness_scale() {
+        let scaled: Vec<f64> = FITNESS.iter().map(|f| f * 1000.0 + 5.0).collect();
+        let tournament = Selection::Tournament { size: 3 };
+        assert_eq!(counts(tournament, &FITNESS, 500), counts(tournament, &scaled, 500));
+        assert_eq!(counts(Selection::Tournament { size: 1 }, &[1.0], 3), vec![3]);
+    }
+
+    #[test]
+    fn test_rank_selection_prefers_the_best() {
+        let linear = counts(Selection::LinearRank { pressure: 2.0 }, &FITNESS, 10_000);
+        // Ranks 0..3 weigh 0, 2/3, 4/3 and 2 out of 4
+        assert_eq!(linear[1], 0);
+        assert!((linear[3] as f64 / 10_000.0 - 0.5).abs() < 0.03, "{linear:?}");
+
+        let exponential = counts(Selection::ExponentialRank { base: 0.5 }, &FITNESS, 10_000);
+        assert!(exponential[3] > exponential[2] && exponential[2] > exponential[0] && exponential[0] > exponential[1]);
+    }
+
+    #[test]
+    fn test_truncation_only_picks_the_best() {
+        let picks = counts(Selection::Truncation { percentage: 50 }, &FITNESS, 1_000);
+        assert_eq!(picks[0] + picks[1], 0);
+        assert!(picks[2] > 0 && picks[3] > 0);
+    }
+
+    #[test]
+    fn test_stochastic_universal_sampling_gives_exact_shares() {
+        assert_eq!(counts(Selection::StochasticUniversal, &FITNESS, 10), vec![1, 0, 3, 6]);
+        assert_eq!(counts(Selection::StochasticUniversal, &[0.0, 0.0], 4), vec![2, 2]);
+    }
+
+    #[test]
+    fn test_invalid_settings_are_rejected() {
+        assert!(Selection::Tournament { size: 0 }.validate().is_err());
+        assert!(Selection::LinearRank { pressure: 2.5 }.validate().is_err());
+        assert!(Selection::ExponentialRank { base: 1.0 }.validate().is_err());
+        assert!(Selection::Truncation { percentage: 0 }.validate().is_err());
+        assert!(Selection::StochasticUniversal.validate().is_ok());
+    }
+}
@@ -1,21 +1,25 @@
+use crate::environment::{ActionSpace, Environment, Observation, ObservationSpace, Step};
+use crate::observation::{ObservationEncoder, ObservationMode};
 use crate::point;
 use point::Point;
-use ndarray::{Array2,array};
+use ndarray::{Array2, Array3, array};
 
-use rand::Rng;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
 
 use std::f64::consts::PI;
 
-const BOARD_SIZE: usize = 18;
-const STEPS_UNTIL_DEATH: usize = 2 * BOARD_SIZE + 1; //Enough to cross the board for the apple
+const DEFAULT_BOARD_WIDTH: usize = 18;
+const DEFAULT_BOARD_HEIGHT: usize = 18;
+const DEFAULT_INITIAL_LENGTH: usize = 4;
 
-const POINTS_PER_APPLE: usize = 300; // 3 times STEPS_UNTIL_DEATH
-const POINTS_PER_STEP: usize = 5;
+const DEFAULT_POINTS_PER_APPLE: usize = 300; // 3 times STEPS_UNTIL_DEATH
+const DEFAULT_POINTS_PER_STEP: usize = 5;
 
-const MAX_APPLES_EATEN: usize = 3;
-const MAX_SCORE: usize = 10000;
+const DEFAULT_MAX_APPLES_EATEN: usize = 3;
+const DEFAULT_MAX_SCORE: usize = 10000;
 
-#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq, Copy, Serialize, Deserialize)]
 pub enum Direction {
     North = 0,
     South = 1,
@@ -23,13 +27,62 @@ pub enum Direction {
     West = 3,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RelativeDirection {
     Left = 0,
     Infront = 1,
     Right = 2,
 }
 
+impl RelativeDirection {
+    pub const COUNT: usize = 3;
+
+    pub fn from_usize(value: usize) -> Self {
+        match value {
+            0 => RelativeDirection::Left,
+            1 => RelativeDirection::Infront,
+            2 => RelativeDirection::Right,
+            _ => panic!("Invalid relative direction value: {}", value),
+        }
+    }
+}
+
+/// How the actions of an agent are turned into moves
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ActionMode {
+    /// An action is a `Direction`; choosing the opposite of the heading is a reversal. The default,
+    /// which is what members saved before the action modes were introduced were trained with.
+    #[default]
+    Absolute,
+    /// An action is a `RelativeDirection` to the heading, so the snake can never reverse
+    Relative,
+}
+
+impl ActionMode {
+    /// Number of actions, which is also the number of outputs of the network
+    pub fn size(self) -> usize {
+        match self {
+            ActionMode::Absolute => Direction::COUNT,
+            ActionMode::Relative => RelativeDirection::COUNT,
+        }
+    }
+
+    /// Direction the snake moves to when taking `action` while heading `heading`
+    pub fn to_direction(self, heading: Direction, action: usize) -> Direction {
+        match self {
+            ActionMode::Absolute => Direction::from_usize(action),
+            ActionMode::Relative => relative_to_absolute(heading, RelativeDirection::from_usize(action)),
+        }
+    }
+
+    pub fn label(self, action: usize) -> String {
+        match self {
+            ActionMode::Absolute => format!("{:?}", Direction::from_usize(action)),
+            ActionMode::Relative => format!("{:?}", RelativeDirection::from_usize(action)),
+        }
+    }
+}
+
 fn relative_to_absolute(dir: Direction, rel: RelativeDirection) -> Direction {
         use Direction::*;
         use RelativeDirection::*;
@@ -54,6 +107,8 @@ fn relative_to_absolute(dir: Direction, rel: RelativeDirection) -> Direction {
     }
 
 impl Direction {
+    pub const COUNT: usize = 4;
+
     pub fn from_usize(value: usize) -> Self {
         match value {
             0 => Direction::North,
@@ -63,6 +118,267 @@ impl Direction {
             _ => panic!("Invalid direction value: {}", value),
         }
     }
+
+    pub fn opposite(self) -> Self {
+        match self {
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::East  => Direction::West,
+            Direction::West  => Direction::East,
+        }
+    }
+
+    /// Returns the point one cell away from `p` in this direction
+    pub fn advance(self, p: Point) -> Point {
+        match self {
+            Direction::North => p.north(),
+            Direction::South => p.south(),
+            Direction::East  => p.east(),
+            Direction::West  => p.west(),
+        }
+    }
+}
+
+/// Number of rays cast by `Snakegame::ray_vision`
+pub const RAY_COUNT: usize = 8;
+
+/// Offsets of the rays, clockwise from north
+const RAY_OFFSETS: [(isize, isize); RAY_COUNT] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+/// What a ray cast from the head sees, each value going from 1.0 when adjacent to the head down
+/// towards 0.0 at the far side of the board, and 0.0 when not seen at all
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayReading {
+    pub wall: f64,
+    pub body: f64,
+    pub apple: f64,
+}
+
+/// Channels of `Snakegame::grid_tensor`
+pub const GRID_CHANNELS: usize = 4;
+pub const GRID_HEAD: usize = 0;
+/// Body segments valued by age, from 1.0 next to the head down towards 0.0 at the tail
+pub const GRID_BODY: usize = 1;
+pub const GRID_APPLE: usize = 2;
+/// Cells outside the board, only seen through a window that reaches past the edge
+pub const GRID_WALL: usize = 3;
+
+/// Which part of the board `Snakegame::grid_tensor` exports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GridOptions {
+    /// Crops an odd sized square centred on the head; the whole board when `None`
+    pub window: Option<usize>,
+    /// Turns the window so the heading points up
+    pub rotate: bool,
+}
+
+impl GridOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        match self.window {
+            Some(size) if size % 2 == 0 => Err(format!("Grid window must have an odd size, got {size}")),
+            None if self.rotate => Err("Rotating the grid needs a window centred on the head".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Rows and columns of the exported grid
+    pub fn dims(&self, config: &GameConfig) -> (usize, usize) {
+        match self.window {
+            Some(size) => (size, size),
+            None => (config.height, config.width),
+        }
+    }
+}
+
+/// Why a game ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameOutcome {
+    /// The head left the board
+    Wall,
+    /// The head moved onto the body
+    SelfCollision,
+    /// The snake tried to turn back onto itself
+    Reversal,
+    /// Too many steps without eating
+    Starvation,
+    /// Ate `max_apples_eaten` apples
+    Won,
+    /// Reached `max_steps`
+    StepLimit,
+}
+
+impl GameOutcome {
+    pub fn description(self) -> &'static str {
+        match self {
+            GameOutcome::Wall => "hit a wall",
+            GameOutcome::SelfCollision => "ran into itself",
+            GameOutcome::Reversal => "reversed into itself",
+            GameOutcome::Starvation => "starvation",
+            GameOutcome::Won => "ate enough apples to win",
+            GameOutcome::StepLimit => "reached the step limit",
+        }
+    }
+}
+
+/// Number of games that ended with each outcome
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutcomeCounts {
+    pub wall: usize,
+    pub self_collision: usize,
+    pub reversal: usize,
+    pub starvation: usize,
+    pub won: usize,
+    pub step_limit: usize,
+}
+
+impl OutcomeCounts {
+    pub fn record(&mut self, outcome: GameOutcome) {
+        match outcome {
+            GameOutcome::Wall => self.wall += 1,
+            GameOutcome::SelfCollision => self.self_collision += 1,
+            GameOutcome::Reversal => self.reversal += 1,
+            GameOutcome::Starvation => self.starvation += 1,
+            GameOutcome::Won => self.won += 1,
+            GameOutcome::StepLimit => self.step_limit += 1,
+        }
+    }
+
+    pub fn add(&mut self, other: &OutcomeCounts) {
+        self.wall += other.wall;
+        self.self_collision += other.self_collision;
+        self.reversal += other.reversal;
+        self.starvation += other.starvation;
+        self.won += other.won;
+        self.step_limit += other.step_limit;
+    }
+
+    pub fn total(&self) -> usize {
+        self.wall + self.self_collision + self.reversal + self.starvation + self.won + self.step_limit
+    }
+}
+
+/// Points awarded during a game and the limits that end it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringConfig {
+    pub points_per_apple: usize,
+    pub points_per_step: usize,
+    /// Steps allowed without eating; derived from the board size when `None`
+    pub steps_until_death: Option<usize>,
+    /// Apples that win the game outright
+    pub max_apples_eaten: usize,
+    /// Score given for winning the game
+    pub max_score: usize,
+    /// Steps after which the game ends even if the snake keeps eating; unlimited when `None`
+    pub max_steps: Option<usize>,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        ScoringConfig {
+            points_per_apple: DEFAULT_POINTS_PER_APPLE,
+            points_per_step: DEFAULT_POINTS_PER_STEP,
+            steps_until_death: None,
+            max_apples_eaten: DEFAULT_MAX_APPLES_EATEN,
+            max_score: DEFAULT_MAX_SCORE,
+            max_steps: None,
+        }
+    }
+}
+
+/// Board dimensions, starting snake and scoring for a game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    pub width: usize,
+    pub height: usize,
+    pub initial_length: usize,
+    /// Position of the snake head at the start of the game
+    pub start: Point,
+    /// Direction the snake is facing at the start; the body trails behind it
+    pub heading: Direction,
+    pub scoring: ScoringConfig,
+    /// Meaning of the actions taken by agents
+    pub action_mode: ActionMode,
+    /// What agents see of the game
+    pub observation: ObservationMode,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            width: DEFAULT_BOARD_WIDTH,
+            height: DEFAULT_BOARD_HEIGHT,
+            initial_length: DEFAULT_INITIAL_LENGTH,
+            start: Point { x: 9, y: 9 },
+            heading: Direction::North,
+            scoring: ScoringConfig::default(),
+            action_mode: ActionMode::default(),
+            observation: ObservationMode::default(),
+        }
+    }
+}
+
+impl GameConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.width < 2 || self.height < 2 {
+            return Err(format!("Board must be at least 2x2, got {}x{}", self.width, self.height));
+        }
+        if self.initial_length == 0 {
+            return Err("Initial snake length must be at least 1".to_string());
+        }
+        if self.initial_length >= self.width * self.height {
+            return Err(format!(
+                "Initial snake length {} leaves no room for an apple on a {}x{} board",
+                self.initial_length, self.width, self.height
+            ));
+        }
+        if let Some(p) = self.initial_snake().iter().find(|p| !self.contains(p)) {
+            return Err(format!("Initial snake segment {} is outside the {}x{} board", p, self.width, self.height));
+        }
+        if self.steps_until_death() == 0 {
+            return Err("Steps until death must be at least 1".to_string());
+        }
+        if self.scoring.max_apples_eaten == 0 {
+            return Err("Max apples eaten must be at least 1".to_string());
+        }
+        self.observation.validate()
+    }
+
+    /// Steps allowed without eating, by default enough to cross the board for the apple
+    pub fn steps_until_death(&self) -> usize {
+        self.scoring
+            .steps_until_death
+            .unwrap_or(2 * self.width.max(self.height) + 1)
+    }
+
+    pub fn contains(&self, p: &Point) -> bool {
+        p.x >= 0 && p.x < self.width as isize && p.y >= 0 && p.y < self.height as isize
+    }
+
+    /// Initial snake ordered from tail to head, trailing behind `start`
+    fn initial_snake(&self) -> Vec<Point> {
+        let behind = self.heading.opposite();
+        let mut snake: Vec<Point> = Vec::with_capacity(self.initial_length);
+        let mut p = self.start;
+        for _ in 0..self.initial_length {
+            snake.push(p);
+            p = behind.advance(p);
+        }
+        snake.reverse();
+        snake
+    }
+}
+
+/// Details of a game returned with every step of the environment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnakeInfo {
+    pub outcome: Option<GameOutcome>,
+    pub score: usize,
+    pub apples_eaten: usize,
+    pub steps: usize,
 }
 
 pub struct Snakegame {
@@ -74,59 +390,50 @@ pub struct Snakegame {
     score: usize,
     snake: Vec<Point>,
     apple_position: Point,
-    pub killed_by_wall: bool,
-    pub killed_by_myself: bool,
-    pub killed_by_hunger: bool,
+    outcome: Option<GameOutcome>,
+    config: GameConfig,
+    rng: StdRng,
 }
 
 impl Snakegame {
-    pub fn new() -> Self{        
-        let snake: Vec<Point> = vec![
-            Point { x: 9, y: 12 },
-            Point { x: 9, y: 11 },
-            Point { x: 9, y: 10 },
-            Point { x: 9, y: 9 }
-            ];
-        let mut rng = rand::rng();
-        let mut apple_position: Point;
-        loop {
-            apple_position = Point { x: rng.random_range(0..BOARD_SIZE) as isize, y: rng.random_range(0..BOARD_SIZE) as isize};
-            if !snake.contains(&apple_position) {
-                break;
-            }
+    /// Creates a game whose apple placements are fully determined by `seed`
+    pub fn with_seed(config: GameConfig, seed: u64) -> Self {
+        if let Err(e) = config.validate() {
+            panic!("Invalid game config: {}", e);
         }
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        let snake: Vec<Point> = config.initial_snake();
+        let apple_position: Point = new_fruit(&snake, &config, &mut rng);
 
         Snakegame {
             apples_eaten: 0,
             alive: true,
-            steps_until_death: STEPS_UNTIL_DEATH,
+            steps_until_death: config.steps_until_death(),
             total_steps: 0,
-            direction: Direction::North,
+            direction: config.heading,
             score: 0,
-            snake: snake,
-            apple_position: apple_position,
-            killed_by_hunger: false,
-            killed_by_myself: false,
-            killed_by_wall: false
+            snake,
+            apple_position,
+            outcome: None,
+            config,
+            rng,
         }
     }
 
     pub fn print_board(&self) {
-        for _ in 0..BOARD_SIZE + 2 {
+        println!("{}", "X".repeat(self.config.width + 2));
+        for y in 0..self.config.height as isize {
             print!("X");
-        }
-        println!("");
-        for row in 0..BOARD_SIZE as isize {
-            print!("X");
-            for col in 0..BOARD_SIZE as isize {
-                if self.get_snake_head_pos().x == row && self.get_snake_head_pos().y == col {
+            for x in 0..self.config.width as isize {
+                let p = Point { x, y };
+                if self.get_snake_head_pos() == p {
                     print!("H");
                 }
-                else if self.snake.contains(&Point { x: (row), y: (col) }) {
+                else if self.snake.contains(&p) {
                     print!("S");
                 }
-                else if self.apple_position.x == row && self.apple_position.y == col {
-                    print!("A");    
+                else if self.apple_position == p {
+                    print!("A");
                 }
                 else {
                     print!(" ");
@@ -134,20 +441,10 @@ impl Snakegame {
             }
             println!("X");
         }
-        for _ in 0..BOARD_SIZE + 2 {
-            print!("X");
-        }
-        println!("");
+        println!("{}", "X".repeat(self.config.width + 2));
     }
 
     pub fn get_current_input(&self) -> Array2<f64> {
-        let dir_input = match self.direction {
-            Direction::North => -1.0,
-            Direction::South => -0.333333333,
-            Direction::East  =>  0.333333333,
-            Direction::West  => 1.0,
-        };
-
         array![
             [self.distance_to_wall(RelativeDirection::Infront)], 
             [self.distance_to_wall(RelativeDirection::Left)],
@@ -178,6 +475,94 @@ impl Snakegame {
         }
         -1.0
     }
+    /// Casts 8 rays from the head, the first one straight ahead and the others clockwise, so the
+    /// readings do not depend on the absolute heading
+    pub fn ray_vision(&self) -> [RayReading; RAY_COUNT] {
+        let ahead = match self.direction {
+            Direction::North => 0,
+            Direction::East => 2,
+            Direction::South => 4,
+            Direction::West => 6,
+        };
+        std::array::from_fn(|i| {
+            let (dx, dy) = RAY_OFFSETS[(ahead + i) % RAY_COUNT];
+            self.cast_ray(dx, dy)
+        })
+    }
+
+    /// Walks from the head by (`dx`, `dy`) until leaving the board. Distances are normalised by the
+    /// longest side of the board so every ray uses the same scale.
+    pub fn cast_ray(&self, dx: isize, dy: isize) -> RayReading {
+        let span = self.config.width.max(self.config.height) as f64;
+        let closeness = |distance: Option<usize>| distance.map_or(0.0, |d| 1.0 - (d - 1) as f64 / span);
+
+        let mut p: Point = self.get_snake_head_pos();
+        let mut body: Option<usize> = None;
+        let mut apple: Option<usize> = None;
+        let mut distance: usize = 0;
+        loop {
+            p = Point { x: p.x + dx, y: p.y + dy };
+            distance += 1;
+            if !self.is_inside_board(&p) {
+                break;
+            }
+            if body.is_none() && self.snake.contains(&p) {
+                body = Some(distance);
+            }
+            if apple.is_none() && self.apple_position == p {
+                apple = Some(distance);
+            }
+        }
+        RayReading {
+            wall: closeness(Some(distance)),
+            body: closeness(body),
+            apple: closeness(apple),
+        }
+    }
+
+    /// Multi-channel picture of the game, indexed by channel, row and column, see `GRID_CHANNELS`
+    pub fn grid_tensor(&self, options: &GridOptions) -> Array3<f64> {
+        let (rows, cols) = options.dims(&self.config);
+        let mut grid: Array3<f64> = Array3::zeros((GRID_CHANNELS, rows, cols));
+        let head = self.get_snake_head_pos();
+        let len = self.snake.len() as f64;
+
+        for row in 0..rows {
+            for col in 0..cols {
+                let p = match options.window {
+                    None => Point { x: col as isize, y: row as isize },
+                    Some(size) => {
+                        let radius = (size / 2) as isize;
+                        let (dx, dy) = (col as isize - radius, row as isize - radius);
+                        if options.rotate {
+                            // Up in the window is ahead of the snake, right is its right hand side
+                            let ahead = self.direction.advance(Point { x: 0, y: 0 });
+                            let right = relative_to_absolute(self.direction, RelativeDirection::Right)
+                                .advance(Point { x: 0, y: 0 });
+                            Point {
+                                x: head.x - dy * ahead.x + dx * right.x,
+                                y: head.y - dy * ahead.y + dx * right.y,
+                            }
+                        } else {
+                            Point { x: head.x + dx, y: head.y + dy }
+                        }
+                    }
+                };
+
+                if !self.is_inside_board(&p) {
+                    grid[[GRID_WALL, row, col]] = 1.0;
+                } else if p == head {
+                    grid[[GRID_HEAD, row, col]] = 1.0;
+                } else if let Some(idx) = self.snake.iter().position(|s| *s == p) {
+                    grid[[GRID_BODY, row, col]] = (idx + 1) as f64 / len;
+                } else if p == self.apple_position {
+                    grid[[GRID_APPLE, row, col]] = 1.0;
+                }
+            }
+        }
+        grid
+    }
+
     pub fn apple_relative_direction(&self) -> f64 {
         // Convert direction to a unit vector
         let dir_vec = match self.direction {
@@ -213,21 +598,17 @@ impl Snakegame {
         let abs_dir = self.direction;
         let abs_target_dir = relative_to_absolute(abs_dir, relative_direction);
 
-        //println!("boardsize {}, head {}", BOARD_SIZE, head);
-
-        let distance = match abs_target_dir {
-            Direction::North => head.y,
-            Direction::South => BOARD_SIZE as isize - head.y,
-            Direction::West  => head.x,
-            Direction::East  => BOARD_SIZE as isize - head.x,
+        let (distance, span) = match abs_target_dir {
+            Direction::North => (head.y, self.config.height),
+            Direction::South => (self.config.height as isize - 1 - head.y, self.config.height),
+            Direction::West  => (head.x, self.config.width),
+            Direction::East  => (self.config.width as isize - 1 - head.x, self.config.width),
         };
 
-        let normalized = 1.0 - (distance as f64 / (BOARD_SIZE - 1) as f64);
+        let normalized = 1.0 - (distance as f64 / (span - 1) as f64);
         normalized.clamp(0.0, 1.0)
     }
 
-    
-
     pub fn distance_to_snake(&self, relative_direction: RelativeDirection) -> f64 {
         let head = self.get_snake_head_pos(); // assuming head is always at index 0
         let abs_dir = self.direction;
@@ -240,8 +621,13 @@ impl Snakegame {
             Direction::West  => Point::west,
         };
 
+        let span = match dir {
+            Direction::North | Direction::South => self.config.height,
+            Direction::East | Direction::West   => self.config.width,
+        };
+
         let mut current = head;
-        for distance in 1..BOARD_SIZE {
+        for distance in 1..span {
             current = advance_fn(current);
 
             if !self.is_inside_board(&current) {
@@ -250,7 +636,7 @@ impl Snakegame {
 
             if self.snake.contains(&current) {
                 // Normalize and invert: 1.0 (close) -> 0.0 (far)
-                return 1.0 - (distance as f64 / (BOARD_SIZE - 1) as f64);
+                return 1.0 - (distance as f64 / (span - 1) as f64);
             }
         }
 
@@ -258,18 +644,18 @@ impl Snakegame {
     }
 
     fn is_inside_board(&self, p: &Point) -> bool {
-        p.x >= 0 && p.x < BOARD_SIZE as isize && p.y >= 0 && p.y < BOARD_SIZE as isize
+        self.config.contains(p)
     }
 
     pub fn distance_to_north_south_wall(&self) -> f64 {
         let y_pos = self.get_snake_head_pos().y as f64;
-        let percentage = (y_pos - 1.0) / (BOARD_SIZE as f64 - 1.0);
+        let percentage = (y_pos - 1.0) / (self.config.height as f64 - 1.0);
         percentage * 2.0 - 1.0
     }
 
     pub fn distance_to_west_east_wall(&self) -> f64 {
         let x_pos = self.get_snake_head_pos().x as f64;
-        let percentage = (x_pos - 1.0) / (BOARD_SIZE as f64 - 1.0);
+        let percentage = (x_pos - 1.0) / (self.config.width as f64 - 1.0);
         percentage * 2.0 - 1.0
     }
 
@@ -298,31 +684,64 @@ impl Snakegame {
     pub fn get_fruit_north_south_distance(&self) -> f64 {
         let head_y = self.get_snake_head_pos().y as f64;
         let apple_y = self.apple_position.y as f64;
-        (head_y - apple_y) / (BOARD_SIZE as f64 - 1.0)
+        (head_y - apple_y) / (self.config.height as f64 - 1.0)
     }
 
     pub fn get_fruit_east_west_distance(&self) -> f64 {
         let head_x = self.get_snake_head_pos().x as f64;
         let apple_x = self.apple_position.x as f64;
-        (head_x - apple_x) / (BOARD_SIZE as f64 - 1.0)
+        (head_x - apple_x) / (self.config.width as f64 - 1.0)
     }
 
-    fn get_snake_head_pos(&self) -> Point {
+    pub fn get_snake_head_pos(&self) -> Point {
         self.snake.last().copied().expect("Snake should never be empty")
     }
 
+    /// Body of the snake from tail to head
+    pub fn snake(&self) -> &[Point] {
+        &self.snake
+    }
+
+    pub fn apple_position(&self) -> Point {
+        self.apple_position
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    /// Input of the network, encoded as chosen by the `observation` of the config
+    pub fn observe(&self) -> Observation {
+        self.config.observation.encode(self)
+    }
+
     pub fn get_score(&self) -> usize {
         self.score
     }
 
-    pub fn move_snake(&mut self, new_direction: Direction) {
-        if new_direction == Direction::North && self.direction == Direction::South ||
-        new_direction == Direction::South && self.direction == Direction::North ||
-        new_direction == Direction::East && self.direction == Direction::West ||
-        new_direction == Direction::West && self.direction == Direction::East {
-            self.alive = false;
-            self.killed_by_myself = true;
-            return;
+    pub fn get_total_steps(&self) -> usize {
+        self.total_steps
+    }
+
+    /// Why the game ended, or `None` while the snake is still alive
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        self.outcome
+    }
+
+    fn end(&mut self, outcome: GameOutcome) -> Option<GameOutcome> {
+        self.alive = false;
+        self.outcome = Some(outcome);
+        self.outcome
+    }
+
+    /// Advances the game one step, returning the outcome once the game is over. Moving a finished
+    /// game does nothing.
+    pub fn move_snake(&mut self, new_direction: Direction) -> Option<GameOutcome> {
+        if !self.alive {
+            return self.outcome;
+        }
+        if new_direction == self.direction.opposite() {
+            return self.end(GameOutcome::Reversal);
         }
         
         self.direction = new_direction;
@@ -339,57 +758,90 @@ impl Snakegame {
         let mut got_apple: bool = false;
 
         if self.apple_position == next_head_position {
-            self.score += POINTS_PER_APPLE;
+            self.score += self.config.scoring.points_per_apple;
             self.apples_eaten += 1;
-            self.steps_until_death = STEPS_UNTIL_DEATH + 1;
+            self.steps_until_death = self.config.steps_until_death() + 1;
             got_apple = true;
         }
-        else if next_head_position.x == -1 || next_head_position.x == BOARD_SIZE as isize ||
-                next_head_position.y == -1 || next_head_position.y == BOARD_SIZE as isize {
-            self.alive = false;
-            self.killed_by_wall;
-            return;
+        else if !self.is_inside_board(&next_head_position) {
+            return self.end(GameOutcome::Wall);
         }
         else if self.snake.contains(&next_head_position) {
-            self.alive = false;
-            self.killed_by_myself = true;
-            return;
+            return self.end(GameOutcome::SelfCollision);
         }
 
         self.snake.push(next_head_position);
-
-        if got_apple {
-            self.apple_position = new_fruit(&self.snake);
-        }
-        else {
+        if !got_apple {
             self.snake.remove(0);
         }
 
+        // A snake filling the board has nowhere left for an apple
+        let board_full: bool = self.snake.len() == self.config.width * self.config.height;
+        if got_apple && !board_full {
+            self.apple_position = new_fruit(&self.snake, &self.config, &mut self.rng);
+        }
+
         self.steps_until_death -= 1;
 
         if self.steps_until_death == 0 {
-            self.alive = false;
-            self.killed_by_hunger = true;
-            return
+            return self.end(GameOutcome::Starvation);
         }
         self.total_steps +=1;
-        self.score += POINTS_PER_STEP;
+        self.score += self.config.scoring.points_per_step;
 
-        if self.apples_eaten == MAX_APPLES_EATEN {
-            self.alive = false;
-            self.score = MAX_SCORE;
-            println!("bingo!")
+        if self.apples_eaten == self.config.scoring.max_apples_eaten || board_full {
+            self.score = self.config.scoring.max_score;
+            return self.end(GameOutcome::Won);
+        }
+        if self.config.scoring.max_steps.is_some_and(|max| self.total_steps >= max) {
+            return self.end(GameOutcome::StepLimit);
         }
+        None
     }
 
 }
 
-fn new_fruit(snake: &Vec<Point>) -> Point {
-        let mut rng = rand::rng();
+impl Environment for Snakegame {
+    type Info = SnakeInfo;
+
+    fn reset(&mut self, seed: u64) -> Observation {
+        *self = Snakegame::with_seed(self.config, seed);
+        self.observe()
+    }
+
+    /// The action is read according to the `ActionMode` of the game, the reward is the score
+    /// gained by the move
+    fn step(&mut self, action: usize) -> Step<SnakeInfo> {
+        let score_before = self.score;
+        let direction = self.config.action_mode.to_direction(self.direction, action);
+        let outcome = self.move_snake(direction);
+        Step {
+            observation: self.observe(),
+            reward: self.score as f64 - score_before as f64,
+            done: outcome.is_some(),
+            info: SnakeInfo {
+                outcome,
+                score: self.score,
+                apples_eaten: self.apples_eaten,
+                steps: self.total_steps,
+            },
+        }
+    }
+
+    fn observation_space(&self) -> ObservationSpace {
+        ObservationSpace { shape: vec![self.config.observation.size(&self.config), 1], low: -1.0, high: 1.0 }
+    }
+
+    fn action_space(&self) -> ActionSpace {
+        ActionSpace::Discrete(self.config.action_mode.size())
+    }
+}
+
+fn new_fruit(snake: &[Point], config: &GameConfig, rng: &mut impl Rng) -> Point {
         loop {
-            let x: isize = rng.random_range(0..BOARD_SIZE) as isize;
-            let y: isize = rng.random_range(0..BOARD_SIZE) as isize;
-            let point: Point = Point { x: x, y: y };
+            let x: isize = rng.random_range(0..config.width) as isize;
+            let y: isize = rng.random_range(0..config.height) as isize;
+            let point: Point = Point { x, y };
             if !snake.contains(&point) {
                 return point;
             }
@@ -408,7 +860,8 @@ mod tests {
             Point { x: 9, y: 10 },
             Point { x: 9, y: 9 }
             ];
-        let fruit = new_fruit(&snake);
+        let config = GameConfig::default();
+        let fruit = new_fruit(&snake, &config, &mut rand::rng());
 
         // Check that the fruit is not on the snake
         assert!(
@@ -419,7 +872,7 @@ mod tests {
 
         // Check that the fruit is within board bounds
         assert!(
-            fruit.x < BOARD_SIZE as isize && fruit.x < BOARD_SIZE as isize && fruit.y < BOARD_SIZE as isize && fruit.y < BOARD_SIZE as isize,
+            config.contains(&fruit),
             "Fruit position {:?} is out of bounds",
             fruit
         );
@@ -427,9 +880,9 @@ mod tests {
 
     #[test]
     fn test_get_snake_head_pos_returns_correct_point() {
-        let game = Snakegame::new();
+        let game = Snakegame::with_seed(GameConfig::default(), 0);
 
-        // The initial snake is hardcoded in Snakegame::new()
+        // The default config starts the snake at (9,9) heading North
         // Last point should be the head
         let expected_head = Point { x: 9, y: 9 };
 
@@ -442,13 +895,31 @@ mod tests {
             expected_head,
             actual_head
         );
+        assert_eq!(game.snake, vec![point(9, 12), point(9, 11), point(9, 10), point(9, 9)]);
     }
 
     fn point(x: isize, y: isize) -> Point {
         Point { x, y }
     }
 
+    fn board(width: usize, height: usize) -> GameConfig {
+        GameConfig {
+            width,
+            height,
+            initial_length: 3,
+            start: point(width as isize / 2, height as isize / 2),
+            heading: Direction::North,
+            scoring: ScoringConfig::default(),
+            action_mode: ActionMode::default(),
+            observation: ObservationMode::default(),
+        }
+    }
+
     fn create_game(snake: Vec<Point>, direction: Direction, apple: Point, steps: usize) -> Snakegame {
+        create_game_on(GameConfig::default(), snake, direction, apple, steps)
+    }
+
+    fn create_game_on(config: GameConfig, snake: Vec<Point>, direction: Direction, apple: Point, steps: usize) -> Snakegame {
         Snakegame {
             apples_eaten: 0,
             alive: true,
@@ -458,27 +929,20 @@ mod tests {
             score: 0,
             snake,
             apple_position: apple,
-            killed_by_hunger: false,
-            killed_by_myself: false,
-            killed_by_wall: false
+            outcome: None,
+            config,
+            rng: StdRng::seed_from_u64(0),
         }
     }
 
     #[test]
     fn valid_movement_without_eating_apple() {
-        let mut game = Snakegame {
-            apples_eaten: 0,
-            alive: true,
-            steps_until_death: STEPS_UNTIL_DEATH,
-            total_steps: 0,
-            direction: Direction::North,
-            score: 0,
-            snake: vec![Point { x: 5, y: 5 }],
-            apple_position: Point { x: 0, y: 0 }, // not at next pos
-            killed_by_hunger: false,
-            killed_by_myself: false,
-            killed_by_wall: false
-        };
+        let mut game = create_game(
+            vec![Point { x: 5, y: 5 }],
+            Direction::North,
+            Point { x: 0, y: 0 }, // not at next pos
+            GameConfig::default().steps_until_death(),
+        );
 
         game.move_snake(Direction::East);
 
@@ -489,19 +953,12 @@ mod tests {
 
     #[test]
     fn valid_movement_snake_grows_when_eating_apple() {
-        let mut game = Snakegame {
-            apples_eaten: 0,
-            alive: true,
-            steps_until_death: STEPS_UNTIL_DEATH,
-            total_steps: 0,
-            direction: Direction::East,
-            score: 0,
-            snake: vec![Point { x: 5, y: 5 }],
-            apple_position: Point { x: 6, y: 5 }, // directly in path
-            killed_by_hunger: false,
-            killed_by_myself: false,
-            killed_by_wall: false
-        };
+        let mut game = create_game(
+            vec![Point { x: 5, y: 5 }],
+            Direction::East,
+            Point { x: 6, y: 5 }, // directly in path
+            GameConfig::default().steps_until_death(),
+        );
 
         game.move_snake(Direction::East);
 
@@ -514,24 +971,25 @@ mod tests {
 
     #[test]
     fn invalid_direction_reversal_kills_snake() {
-        let mut game = create_game(vec![point(5, 5)], Direction::North, point(0, 0), STEPS_UNTIL_DEATH);
-        game.move_snake(Direction::South);
+        let mut game = create_game(vec![point(5, 5)], Direction::North, point(0, 0), GameConfig::default().steps_until_death());
+        assert_eq!(game.move_snake(Direction::South), Some(GameOutcome::Reversal));
         assert!(!game.alive, "Snake should die when reversing direction");
     }
 
     #[test]
     fn steps_until_death_kills_snake() {
         let mut game = create_game(vec![point(5, 5)], Direction::East, point(0, 0), 1);
-        game.move_snake(Direction::East);
+        assert_eq!(game.move_snake(Direction::East), Some(GameOutcome::Starvation));
         assert!(!game.alive, "Snake should die when out of steps");
     }
 
     #[test]
     fn wall_collision_kills_snake() {
-        // Point at top wall, moving north will .saturating_sub(1) to 0, which is same as head
-        let mut game = create_game(vec![point(5, 0)], Direction::North, point(0, 0), STEPS_UNTIL_DEATH);
+        // Point at top wall, moving north leaves the board
+        let mut game = create_game(vec![point(5, 0)], Direction::North, point(0, 0), GameConfig::default().steps_until_death());
         game.move_snake(Direction::North);
-        assert!(!game.alive, "Snake should die if it doesn't move (hits wall/self)");
+        assert!(!game.alive, "Snake should die if it leaves the board");
+        assert_eq!(game.outcome(), Some(GameOutcome::Wall), "Leaving the board should be recorded as a wall kill");
     }
 
     #[test]
@@ -541,127 +999,65 @@ mod tests {
             point(4, 3), point(3, 3), point(2, 3),
         ];
         let mut game = create_game(snake, Direction::East, point(0, 0), 10);
-        game.move_snake(Direction::North); // (2,3) → (2,2), hits body
+        assert_eq!(game.move_snake(Direction::North), Some(GameOutcome::SelfCollision)); // (2,3) → (2,2), hits body
         assert!(!game.alive, "Snake should die colliding into itself");
     }
 
-        #[test]
+    #[test]
     fn test_fruit_east_west_distance_left_of_head() {
-        let game = Snakegame {
-            apples_eaten: 0,
-            alive: true,
-            steps_until_death: 10,
-            total_steps: 0,
-            direction: Direction::East,
-            score: 0,
-            snake: vec![Point { x: 10, y: 5 }],
-            apple_position: Point { x: 4, y: 5 },
-            killed_by_hunger: false,
-            killed_by_myself: false,
-            killed_by_wall: false
-        };
+        let game = create_game(vec![Point { x: 10, y: 5 }], Direction::East, Point { x: 4, y: 5 }, 10);
 
         let dist = game.get_fruit_east_west_distance();
-        let expected = (10.0 - 4.0) / (BOARD_SIZE as f64 - 1.0);
+        let expected = (10.0 - 4.0) / (GameConfig::default().width as f64 - 1.0);
         assert!((dist - expected).abs() < 1e-6);
     }
 
     #[test]
     fn test_fruit_east_west_distance_right_of_head() {
-        let game = Snakegame {
-            apples_eaten: 0,
-            alive: true,
-            steps_until_death: 10,
-            total_steps: 0,
-            direction: Direction::East,
-            score: 0,
-            snake: vec![Point { x: 4, y: 5 }],
-            apple_position: Point { x: 10, y: 5 },
-            killed_by_hunger: false,
-            killed_by_myself: false,
-            killed_by_wall: false
-        };
+        let game = create_game(vec![Point { x: 4, y: 5 }], Direction::East, Point { x: 10, y: 5 }, 10);
 
         let dist = game.get_fruit_east_west_distance();
-        let expected = (4.0 - 10.0) / (BOARD_SIZE as f64 - 1.0);
+        let expected = (4.0 - 10.0) / (GameConfig::default().width as f64 - 1.0);
         assert!((dist - expected).abs() < 1e-6);
     }
 
     #[test]
     fn test_distance_fruit_infront_one_step() {
-        let game = Snakegame {
-            apples_eaten: 0,
-            alive: true,
-            steps_until_death: 10,
-            total_steps: 0,
-            direction: Direction::East,
-            score: 0,
-            snake: vec![Point { x: 3, y: 5 }],
-            apple_position: Point { x: 4, y: 5 },
-            killed_by_hunger: false,
-            killed_by_myself: false,
-            killed_by_wall: false
-        };
+        let game = create_game(vec![Point { x: 3, y: 5 }], Direction::East, Point { x: 4, y: 5 }, 10);
 
         assert_eq!(game.distance_fruit_infront(), 1.0);
     }
 
     #[test]
     fn test_distance_fruit_infront_four_steps() {
-        let game = Snakegame {
-            apples_eaten: 0,
-            alive: true,
-            steps_until_death: 10,
-            total_steps: 0,
-            direction: Direction::North,
-            score: 0,
-            snake: vec![Point { x: 4, y: 6 }],
-            apple_position: Point { x: 4, y: 2 },
-            killed_by_hunger: false,
-            killed_by_myself: false,
-            killed_by_wall: false
-        };
+        let game = create_game(vec![Point { x: 4, y: 6 }], Direction::North, Point { x: 4, y: 2 }, 10);
 
         assert_eq!(game.distance_fruit_infront(), 0.4);
     }
 
     #[test]
     fn test_distance_fruit_infront_not_in_path() {
-        let game = Snakegame {
-            apples_eaten: 0,
-            alive: true,
-            steps_until_death: 10,
-            total_steps: 0,
-            direction: Direction::West,
-            score: 0,
-            snake: vec![Point { x: 10, y: 10 }],
-            apple_position: Point { x: 3, y: 9 }, // not in direct path
-            killed_by_hunger: false,
-            killed_by_myself: false,
-            killed_by_wall: false
-        };
+        let game = create_game(
+            vec![Point { x: 10, y: 10 }],
+            Direction::West,
+            Point { x: 3, y: 9 }, // not in direct path
+            10,
+        );
 
         assert_eq!(game.distance_fruit_infront(), -1.0);
     }
 
     #[test]
     fn test_snake_immediately_north() {
-        let game = Snakegame {
-            apples_eaten: 0,
-            alive: true,
-            steps_until_death: 10,
-            total_steps: 0,
-            direction: Direction::North,
-            score: 0,
-            snake: vec![
+        let game = create_game(
+            vec![
                 Point { x: 5, y: 4 }, // ← directly north
                 Point { x: 5, y: 5 }, // head
             ],
-            apple_position: Point { x: 0, y: 0 },
-            killed_by_hunger: false,
-            killed_by_myself: false,
-            killed_by_wall: false
-        };
+            Direction::North,
+            Point { x: 0, y: 0 },
+            10,
+        );
 
         let score = game.have_snake_in_direction(Direction::North);
         assert_eq!(score, 1.0);
@@ -669,22 +1065,15 @@ mod tests {
 
     #[test]
     fn test_snake_three_steps_east() {
-        let game = Snakegame {
-            apples_eaten: 0,
-            alive: true,
-            steps_until_death: 10,
-            total_steps: 0,
-            direction: Direction::East,
-            score: 0,
-            snake: vec![
+        let game = create_game(
+            vec![
                 Point { x: 8, y: 5 }, // 3 steps east
                 Point { x: 5, y: 5 }, // head
             ],
-            apple_position: Point { x: 0, y: 0 },
-            killed_by_hunger: false,
-            killed_by_myself: false,
-            killed_by_wall: false
-        };
+            Direction::East,
+            Point { x: 0, y: 0 },
+            10,
+        );
 
         let score = game.have_snake_in_direction(Direction::East);
         assert_eq!(score, 0.5);
@@ -692,22 +1081,15 @@ mod tests {
 
     #[test]
     fn test_snake_four_steps_south() {
-        let game = Snakegame {
-            apples_eaten: 0,
-            alive: true,
-            steps_until_death: 10,
-            total_steps: 0,
-            direction: Direction::South,
-            score: 0,
-            snake: vec![
+        let game = create_game(
+            vec![
                 Point { x: 10, y: 14 }, // 4 steps south
                 Point { x: 10, y: 10 }, // head
             ],
-            apple_position: Point { x: 0, y: 0 },
-            killed_by_hunger: false,
-            killed_by_myself: false,
-            killed_by_wall: false
-        };
+            Direction::South,
+            Point { x: 0, y: 0 },
+            10,
+        );
 
         let score = game.have_snake_in_direction(Direction::South);
         assert_eq!(score, 0.1);
@@ -715,22 +1097,15 @@ mod tests {
 
     #[test]
     fn test_no_snake_in_direction() {
-        let game = Snakegame {
-            apples_eaten: 0,
-            alive: true,
-            steps_until_death: 10,
-            total_steps: 0,
-            direction: Direction::West,
-            score: 0,
-            snake: vec![
+        let game = create_game(
+            vec![
                 Point { x: 2, y: 5 }, // far away
                 Point { x: 10, y: 10 }, // head
             ],
-            apple_position: Point { x: 0, y: 0 },
-            killed_by_hunger: false,
-            killed_by_myself: false,
-            killed_by_wall: false
-        };
+            Direction::West,
+            Point { x: 0, y: 0 },
+            10,
+        );
 
         let score = game.have_snake_in_direction(Direction::West);
         assert_eq!(score, -1.0);
@@ -738,22 +1113,15 @@ mod tests {
 
     #[test]
     fn test_snake_two_steps_west() {
-        let game = Snakegame {
-            apples_eaten: 0,
-            alive: true,
-            steps_until_death: 10,
-            total_steps: 0,
-            direction: Direction::West,
-            score: 0,
-            snake: vec![
+        let game = create_game(
+            vec![
                 Point { x: 3, y: 5 }, // 2 steps west
                 Point { x: 5, y: 5 }, // head
             ],
-            apple_position: Point { x: 0, y: 0 },
-            killed_by_hunger: false,
-            killed_by_myself: false,
-            killed_by_wall: false
-        };
+            Direction::West,
+            Point { x: 0, y: 0 },
+            10,
+        );
 
         let score = game.have_snake_in_direction(Direction::West);
         assert_eq!(score, 0.8);
@@ -761,34 +1129,337 @@ mod tests {
 
     #[test]
     fn test_distance_to_wall_infront() {
-        let mut game = Snakegame::new();
+        let mut game = Snakegame::with_seed(board(10, 10), 0);
         game.direction = Direction::North;
         game.snake = vec![Point { x: 5, y: 5 }];
-        
+
         let d = game.distance_to_wall(RelativeDirection::Infront);
-        assert!((d - (1.0 - (4.0 / 9.0))).abs() < 1e-6); // 1.0 - (4 / 9) if BOARD_SIZE is 10
+        assert!((d - (1.0 - (5.0 / 9.0))).abs() < 1e-6); // 5 free cells north on a 10x10 board
     }
 
     #[test]
-        fn test_distance_to_snake_infront() {
-        let game = Snakegame {
-            apples_eaten: 0,
-            alive: true,
-            steps_until_death: 100,
-            total_steps: 0,
-            direction: Direction::East,
-            score: 0,
-            killed_by_wall: false,
-            killed_by_myself: false,
-            killed_by_hunger: false,
-            snake: vec![
-                Point { x: 5, y: 5 }, // head
+    fn test_distance_to_snake_infront() {
+        let game = create_game(
+            vec![
                 Point { x: 6, y: 5 }, // body in front (East)
+                Point { x: 5, y: 5 }, // head
             ],
-            apple_position: Point { x: 0, y: 0 },
+            Direction::East,
+            Point { x: 0, y: 0 },
+            100,
+        );
+
+        let d = game.distance_to_snake(RelativeDirection::Infront);
+        assert!((d - (1.0 - 1.0 / 17.0)).abs() < 1e-6);
+    }
+
+    fn play_moves(game: &mut Snakegame, moves: &[Direction]) -> Vec<Point> {
+        let mut apples = vec![game.apple_position];
+        for &m in moves {
+            if !game.alive {
+                break;
+            }
+            game.move_snake(m);
+            apples.push(game.apple_position);
+        }
+        apples
+    }
+
+    #[test]
+    fn test_same_seed_and_moves_give_same_episode() {
+        use Direction::*;
+        let moves = [North, East, East, South, South, West, South, East, East, North, North, North];
+
+        let mut g1 = Snakegame::with_seed(board(6, 6), 42);
+        let mut g2 = Snakegame::with_seed(board(6, 6), 42);
+        let apples1 = play_moves(&mut g1, &moves);
+        let apples2 = play_moves(&mut g2, &moves);
+
+        assert_eq!(apples1, apples2);
+        assert_eq!(g1.snake, g2.snake);
+        assert_eq!(g1.get_score(), g2.get_score());
+        assert_eq!(g1.alive, g2.alive);
+    }
+
+    #[test]
+    fn test_different_seeds_place_apples_differently() {
+        let first_apples: Vec<Point> = (0..20)
+            .map(|seed| Snakegame::with_seed(GameConfig::default(), seed).apple_position)
+            .collect();
+        assert!(first_apples.iter().any(|p| *p != first_apples[0]));
+    }
+
+    #[test]
+    fn test_apple_respawn_follows_seed() {
+        // Apple directly ahead so the next placement is drawn from the game's rng
+        let config = board(10, 30);
+        let mut g1 = create_game_on(config, vec![point(5, 5)], Direction::East, point(6, 5), 10);
+        let mut g2 = create_game_on(config, vec![point(5, 5)], Direction::East, point(6, 5), 10);
+        g1.rng = StdRng::seed_from_u64(99);
+        g2.rng = StdRng::seed_from_u64(99);
+        g1.move_snake(Direction::East);
+        g2.move_snake(Direction::East);
+
+        assert_eq!(g1.apples_eaten, 1);
+        assert_eq!(g1.apple_position, g2.apple_position);
+    }
+
+    #[test]
+    fn test_scoring_config_is_honoured() {
+        let mut config = board(10, 10);
+        config.scoring = ScoringConfig {
+            points_per_apple: 50,
+            points_per_step: 1,
+            steps_until_death: Some(4),
+            max_apples_eaten: 2,
+            max_score: 777,
+            max_steps: None,
         };
+        assert_eq!(config.steps_until_death(), 4);
+
+        let mut game = create_game_on(config, vec![point(5, 5)], Direction::East, point(6, 5), 4);
+        game.move_snake(Direction::East);
+        assert_eq!(game.get_score(), 51);
+
+        game.apple_position = point(7, 5);
+        assert_eq!(game.move_snake(Direction::East), Some(GameOutcome::Won));
+        assert!(!game.alive);
+        assert_eq!(game.get_score(), 777);
+    }
+
+    #[test]
+    fn test_outcome_reports_wall() {
+        let mut game = create_game(vec![point(5, 0)], Direction::North, point(0, 0), 10);
+        assert_eq!(game.outcome(), None);
+        game.move_snake(Direction::North);
+        assert_eq!(game.outcome().map(GameOutcome::description), Some("hit a wall"));
+    }
+
+    #[test]
+    fn test_step_limit_ends_game() {
+        let mut config = board(10, 10);
+        config.scoring.max_steps = Some(2);
+        let mut game = create_game_on(config, vec![point(5, 5)], Direction::East, point(0, 0), 10);
+        assert_eq!(game.move_snake(Direction::East), None);
+        assert_eq!(game.move_snake(Direction::East), Some(GameOutcome::StepLimit));
+        assert!(!game.alive);
+    }
+
+    #[test]
+    fn test_filling_the_board_wins() {
+        let mut config = board(2, 2);
+        config.scoring.max_apples_eaten = 10;
+        let mut game = create_game_on(config, vec![point(0, 0)], Direction::East, point(1, 0), 10);
+        assert_eq!(game.move_snake(Direction::East), None);
+        assert_eq!(game.move_snake(Direction::South), None);
+        assert_eq!(game.move_snake(Direction::West), Some(GameOutcome::Won));
+        assert_eq!(game.apples_eaten, 3);
+        // The last apple grows the snake like any other, so the board is reported full
+        assert_eq!(game.snake().len(), 4);
+    }
+
+    #[test]
+    fn test_finished_game_does_not_move() {
+        let mut game = create_game(vec![point(5, 0)], Direction::North, point(0, 0), 10);
+        game.move_snake(Direction::North);
+        assert_eq!(game.move_snake(Direction::East), Some(GameOutcome::Wall));
+        assert_eq!(game.get_snake_head_pos(), point(5, 0));
+        assert_eq!(game.get_total_steps(), 0);
+    }
+
+    #[test]
+    fn test_outcome_counts() {
+        let mut counts = OutcomeCounts::default();
+        counts.record(GameOutcome::Wall);
+        counts.record(GameOutcome::Wall);
+        counts.record(GameOutcome::Reversal);
+        let mut total = OutcomeCounts::default();
+        total.add(&counts);
+        total.record(GameOutcome::Won);
+        assert_eq!(total.wall, 2);
+        assert_eq!(total.reversal, 1);
+        assert_eq!(total.won, 1);
+        assert_eq!(total.self_collision, 0);
+        assert_eq!(total.total(), 4);
+    }
+
+    #[test]
+    fn test_ray_vision_readings() {
+        let snake = vec![point(5, 7), point(5, 6), point(5, 5)];
+        let game = create_game_on(board(10, 10), snake, Direction::North, point(8, 2), 10);
+        let rays = game.ray_vision();
+
+        // Ahead the wall is 6 steps away, behind the body is adjacent
+        assert!((rays[0].wall - (1.0 - 5.0 / 10.0)).abs() < 1e-9);
+        assert_eq!(rays[0].body, 0.0);
+        assert_eq!(rays[4].body, 1.0);
+        // The apple sits 3 cells away on the north-east diagonal
+        assert!((rays[1].apple - (1.0 - 2.0 / 10.0)).abs() < 1e-9);
+        assert!(rays.iter().enumerate().all(|(i, r)| i == 1 || r.apple == 0.0));
+    }
+
+    #[test]
+    fn test_ray_vision_turns_with_heading() {
+        let snake = vec![point(3, 5), point(4, 5), point(5, 5)];
+        let game = create_game_on(board(10, 10), snake, Direction::East, point(8, 5), 10);
+        let rays = game.ray_vision();
+
+        assert!(rays[0].apple > 0.0, "the first ray looks east when heading east");
+        assert_eq!(rays[4].body, 1.0);
+        assert_eq!(rays[2].wall, game.cast_ray(0, 1).wall);
+    }
+
+    #[test]
+    fn test_grid_tensor_of_whole_board() {
+        let snake = vec![point(2, 5), point(3, 5), point(4, 5)];
+        let game = create_game_on(board(10, 6), snake, Direction::East, point(8, 1), 10);
+        let grid = game.grid_tensor(&GridOptions::default());
+
+        assert_eq!(grid.shape(), &[GRID_CHANNELS, 6, 10]);
+        assert_eq!(grid[[GRID_HEAD, 5, 4]], 1.0);
+        assert_eq!(grid[[GRID_APPLE, 1, 8]], 1.0);
+        // The neck is younger than the tail
+        assert!(grid[[GRID_BODY, 5, 3]] > grid[[GRID_BODY, 5, 2]]);
+        assert_eq!(grid[[GRID_BODY, 5, 4]], 0.0);
+        assert_eq!(grid.index_axis(ndarray::Axis(0), GRID_WALL).sum(), 0.0);
+    }
+
+    #[test]
+    fn test_grid_tensor_window_reaches_past_walls() {
+        let game = create_game_on(board(10, 10), vec![point(0, 0)], Direction::North, point(9, 9), 10);
+        let options = GridOptions { window: Some(3), rotate: false };
+        let grid = game.grid_tensor(&options);
+
+        assert_eq!(grid.shape(), &[GRID_CHANNELS, 3, 3]);
+        assert_eq!(grid[[GRID_HEAD, 1, 1]], 1.0);
+        assert_eq!(grid.index_axis(ndarray::Axis(0), GRID_WALL).sum(), 5.0);
+    }
+
+    #[test]
+    fn test_grid_tensor_rotates_to_heading() {
+        // Heading east with the apple two cells ahead and the body behind
+        let snake = vec![point(3, 5), point(4, 5), point(5, 5)];
+        let game = create_game_on(board(10, 10), snake, Direction::East, point(7, 5), 10);
+        let options = GridOptions { window: Some(5), rotate: true };
+        let grid = game.grid_tensor(&options);
+
+        assert_eq!(grid[[GRID_HEAD, 2, 2]], 1.0);
+        assert_eq!(grid[[GRID_APPLE, 0, 2]], 1.0, "ahead is up");
+        assert!(grid[[GRID_BODY, 3, 2]] > 0.0, "behind is down");
+        assert!(options.validate().is_ok());
+        assert!(GridOptions { window: None, rotate: true }.validate().is_err());
+    }
+
+    #[test]
+    fn test_relative_actions_turn_from_heading() {
+        let config = GameConfig { action_mode: ActionMode::Relative, ..GameConfig::default() };
+        let mut game = create_game_on(config, vec![point(5, 5)], Direction::North, point(0, 0), 10);
+        assert_eq!(game.step(RelativeDirection::Left as usize).info.outcome, None);
+        assert_eq!(game.get_snake_head_pos(), point(4, 5), "left of north is west");
+        game.step(RelativeDirection::Infront as usize);
+        assert_eq!(game.get_snake_head_pos(), point(3, 5));
+        game.step(RelativeDirection::Right as usize);
+        assert_eq!(game.get_snake_head_pos(), point(3, 4), "right of west is north");
+        assert_eq!(game.action_space().size(), 3);
+    }
+
+    #[test]
+    fn test_absolute_actions_reach_every_direction() {
+        let mut game = create_game(vec![point(5, 5)], Direction::North, point(0, 0), 10);
+        game.step(Direction::West as usize);
+        assert_eq!(game.get_snake_head_pos(), point(4, 5));
+        assert_eq!(game.step(Direction::East as usize).info.outcome, Some(GameOutcome::Reversal));
+        assert_eq!(game.action_space().size(), 4);
+    }
+
+    #[test]
+    fn test_game_config_initial_snake_trails_heading() {
+        let config = GameConfig {
+            width: 10,
+            height: 30,
+            initial_length: 3,
+            start: point(2, 20),
+            heading: Direction::East,
+            scoring: ScoringConfig::default(),
+            action_mode: ActionMode::default(),
+            observation: ObservationMode::default(),
+        };
+        let game = Snakegame::with_seed(config, 0);
+
+        assert_eq!(game.snake, vec![point(0, 20), point(1, 20), point(2, 20)]);
+        assert_eq!(game.direction, Direction::East);
+    }
+
+    #[test]
+    fn test_game_config_rejects_snake_outside_board() {
+        let config = GameConfig {
+            width: 10,
+            height: 30,
+            initial_length: 4,
+            start: point(2, 20),
+            heading: Direction::East,
+            scoring: ScoringConfig::default(),
+            action_mode: ActionMode::default(),
+            observation: ObservationMode::default(),
+        };
+        assert!(config.validate().is_err());
+        assert!(board(10, 30).validate().is_ok());
+        assert!(board(1, 30).validate().is_err());
+    }
+
+    #[test]
+    fn test_new_fruit_inside_non_square_board() {
+        let config = board(10, 30);
+        let snake = config.initial_snake();
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut max_x = 0;
+        let mut max_y = 0;
+        for _ in 0..2000 {
+            let fruit = new_fruit(&snake, &config, &mut rng);
+            assert!(config.contains(&fruit), "Fruit position {:?} is out of bounds", fruit);
+            assert!(!snake.contains(&fruit));
+            max_x = max_x.max(fruit.x);
+            max_y = max_y.max(fruit.y);
+        }
+        // The tall side of the board is actually used
+        assert!(max_y > max_x);
+    }
+
+    #[test]
+    fn test_wall_collision_on_non_square_board() {
+        let config = board(10, 30);
+
+        let mut game = create_game_on(config, vec![point(9, 5)], Direction::East, point(0, 0), 10);
+        game.move_snake(Direction::East);
+        assert_eq!(game.outcome(), Some(GameOutcome::Wall), "x = 10 is outside a 10 wide board");
+
+        let mut game = create_game_on(config, vec![point(5, 28)], Direction::South, point(0, 0), 10);
+        game.move_snake(Direction::South);
+        assert!(game.alive, "y = 29 is inside a 30 tall board");
+        game.move_snake(Direction::South);
+        assert_eq!(game.outcome(), Some(GameOutcome::Wall), "y = 30 is outside a 30 tall board");
+    }
+
+    #[test]
+    fn test_distance_to_wall_on_non_square_board() {
+        let config = board(10, 30);
+        let game = create_game_on(config, vec![point(0, 0)], Direction::South, point(5, 5), 10);
+
+        // Heading south from the top-left corner: the whole board height is ahead
+        assert_eq!(game.distance_to_wall(RelativeDirection::Infront), 1.0 - 29.0 / 29.0);
+        // West is right next to the head (Right of South)
+        assert_eq!(game.distance_to_wall(RelativeDirection::Right), 1.0);
+        // East spans the board width (Left of South)
+        assert_eq!(game.distance_to_wall(RelativeDirection::Left), 1.0 - 9.0 / 9.0);
+    }
+
+    #[test]
+    fn test_distance_to_snake_on_non_square_board() {
+        let config = board(10, 30);
+        let game = create_game_on(config, vec![point(3, 29), point(3, 2)], Direction::South, point(5, 5), 10);
 
+        // Tail 27 cells south of the head, scanned along the 30 tall axis
         let d = game.distance_to_snake(RelativeDirection::Infront);
-        assert!((d - 1.0).abs() < 1e-6);
+        assert!((d - (1.0 - 27.0 / 29.0)).abs() < 1e-9);
     }
 }
@@ -0,0 +1,322 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::snakegame::OutcomeCounts;
+
+pub const STATS_JSONL_FILE: &str = "stats.jsonl";
+pub const STATS_CSV_FILE: &str = "stats.csv";
+
+const CSV_HEADER: &str = "generation,members,games,min_fitness,median_fitness,max_fitness,mean_fitness,\
+stddev_fitness,wall,self_collision,reversal,starvation,won,step_limit,apples_eaten,\
+apples_per_game,steps_per_game,distinct_fitness,genome_spread,mean_age,eval_seconds";
+
+/// What the stats need from an individual after its games, implemented by every kind of genome
+pub trait Evaluated {
+    fn fitness(&self) -> f64;
+
+    /// Generation the individual was created in
+    fn generation(&self) -> usize;
+
+    fn outcomes(&self) -> &OutcomeCounts;
+
+    fn apples_eaten(&self) -> usize;
+
+    fn steps_survived(&self) -> usize;
+
+    /// Every evolved value, in a fixed order for individuals of the same shape
+    fn parameters(&self) -> Vec<f64>;
+}
+
+/// Summary of one evaluated generation, one row of the stats log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenerationStats {
+    pub generation: usize,
+    pub members: usize,
+    /// Games played by the whole population
+    pub games: usize,
+    pub min_fitness: f64,
+    pub median_fitness: f64,
+    pub max_fitness: f64,
+    pub mean_fitness: f64,
+    pub stddev_fitness: f64,
+    /// How the games of the generation ended
+    pub outcomes: OutcomeCounts,
+    pub apples_eaten: usize,
+    pub apples_per_game: f64,
+    pub steps_per_game: f64,
+    /// Number of different fitness values, a collapse towards 1 means the population converged
+    pub distinct_fitness: usize,
+    /// Mean euclidean distance of the members' weights and biases to their average
+    pub genome_spread: f64,
+    /// Mean number of generations since each member was created
+    pub mean_age: f64,
+    /// Wall-clock time spent evaluating the generation
+    pub eval_seconds: f64,
+}
+
+impl GenerationStats {
+    /// Aggregates the fitness and game stats left on the members by `iterate_to_update_fitness`
+    pub fn from_members<T: Evaluated>(generation: usize, members: &[T], eval_seconds: f64) -> Self {
+        let mut fitness: Vec<f64> = members.iter().map(|m| m.fitness()).collect();
+        fitness.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        let n = members.len();
+        let mean_fitness = mean(&fitness);
+        let variance = if n == 0 {
+            0.0
+        } else {
+            fitness.iter().map(|f| (f - mean_fitness).powi(2)).sum::<f64>() / n as f64
+        };
+        let median = match n {
+            0 => 0.0,
+            _ if n % 2 == 1 => fitness[n / 2],
+            _ => (fitness[n / 2 - 1] + fitness[n / 2]) / 2.0,
+        };
+        let mut distinct = fitness.clone();
+        distinct.dedup();
+
+        let apples_eaten: usize = members.iter().map(|m| m.apples_eaten()).sum();
+        let steps: usize = members.iter().map(|m| m.steps_survived()).sum();
+        let mut outcomes = OutcomeCounts::default();
+        for member in members {
+            outcomes.add(member.outcomes());
+        }
+        let games = outcomes.total();
+        let ages: Vec<f64> = members
+            .iter()
+            .map(|m| generation.saturating_sub(m.generation()) as f64)
+            .collect();
+
+        GenerationStats {
+            generation,
+            members: n,
+            games,
+            min_fitness: fitness.first().copied().unwrap_or(0.0),
+            median_fitness: median,
+            max_fitness: fitness.last().copied().unwrap_or(0.0),
+            mean_fitness,
+            stddev_fitness: variance.sqrt(),
+            outcomes,
+            apples_eaten,
+            apples_per_game: per_game(apples_eaten, games),
+            steps_per_game: per_game(steps, games),
+            distinct_fitness: distinct.len(),
+            genome_spread: genome_spread(members),
+            mean_age: mean(&ages),
+            eval_seconds,
+        }
+    }
+
+    /// One line overview printed after every generation
+    pub fn summary(&self) -> String {
+        format!(
+            "max(Fit): {:.0}, median(Fit): {:.0}, avg(Fit): {:.0}, K by wall: {}, K by myself: {}, K by reversal: {}, K by hunger: {}, Won: {}, Apples eaten: {}",
+            self.max_fitness,
+            self.median_fitness,
+            self.mean_fitness,
+            self.outcomes.wall,
+            self.outcomes.self_collision,
+            self.outcomes.reversal,
+            self.outcomes.starvation,
+            self.outcomes.won,
+            self.apples_eaten,
+        )
+    }
+
+    fn to_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.generation,
+            self.members,
+            self.games,
+            self.min_fitness,
+            self.median_fitness,
+            self.max_fitness,
+            self.mean_fitness,
+            self.stddev_fitness,
+            self.outcomes.wall,
+            self.outcomes.self_collision,
+            self.outcomes.reversal,
+            self.outcomes.starvation,
+            self.outcomes.won,
+            self.outcomes.step_limit,
+            self.apples_eaten,
+            self.apples_per_game,
+            self.steps_per_game,
+            self.distinct_fitness,
+            self.genome_spread,
+            self.mean_age,
+            self.eval_seconds,
+        )
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn per_game(total: usize, games: usize) -> f64 {
+    if games == 0 {
+        return 0.0;
+    }
+    total as f64 / games as f64
+}
+
+/// Only members shaped like the first one are compared, a parameter vector of another length has
+/// no meaningful distance to the average
+fn genome_spread<T: Evaluated>(members: &[T]) -> f64 {
+    let genomes: Vec<Vec<f64>> = members.iter().map(Evaluated::parameters).collect();
+    let Some(len) = genomes.first().map(Vec::len) else {
+        return 0.0;
+    };
+    let genomes: Vec<&Vec<f64>> = genomes.iter().filter(|g| g.len() == len).collect();
+
+    let mut centroid = vec![0.0; len];
+    for genome in &genomes {
+        for (c, v) in centroid.iter_mut().zip(genome.iter()) {
+            *c += v / genomes.len() as f64;
+        }
+    }
+    let distances: Vec<f64> = genomes
+        .iter()
+        .map(|g| g.iter().zip(&centroid).map(|(v, c)| (v - c).powi(2)).sum::<f64>().sqrt())
+        .collect();
+    mean(&distances)
+}
+
+/// Appends a row per generation to `stats.jsonl` and `stats.csv` in the output directory of a run
+pub struct StatsLog {
+    jsonl: File,
+    csv: File,
+}
+
+impl StatsLog {
+    /// Opens the logs of `out_dir` for appending. Rows of `from_generation` onwards are dropped
+    /// first, so a run resumed from an older checkpoint does not log the same generation twice.
+    pub fn open(out_dir: &Path, from_generation: usize) -> std::io::Result<Self> {
+        let jsonl_path = out_dir.join(STATS_JSONL_FILE);
+        let csv_path = out_dir.join(STATS_CSV_FILE);
+        truncate_from(&jsonl_path, from_generation, |line| {
+            serde_json::from_str::<GenerationStats>(line).ok().map(|s| s.generation)
+        })?;
+        truncate_from(&csv_path, from_generation, |line| line.split(',').next()?.parse().ok())?;
+
+        let jsonl = OpenOptions::new().create(true).append(true).open(&jsonl_path)?;
+        let mut csv = OpenOptions::new().create(true).append(true).open(&csv_path)?;
+        if csv.metadata()?.len() == 0 {
+            writeln!(csv, "{CSV_HEADER}")?;
+        }
+        Ok(StatsLog { jsonl, csv })
+    }
+
+    pub fn append(&mut self, stats: &GenerationStats) -> std::io::Result<()> {
+        writeln!(self.jsonl, "{}", serde_json::to_string(stats)?)?;
+        writeln!(self.csv, "{}", stats.to_csv_row())
+    }
+}
+
+/// Keeps the lines of `path` whose generation is below `generation`, and lines without one such as
+/// the CSV header
+fn truncate_from(path: &Path, generation: usize, generation_of: impl Fn(&str) -> Option<usize>) -> std::io::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let kept: Vec<String> = BufReader::new(File::open(path)?)
+        .lines()
+        .collect::<std::io::Result<Vec<String>>>()?
+        .into_iter()
+        .filter(|line| generation_of(line).is_none_or(|g| g < generation))
+        .collect();
+    let mut file = File::create(path)?;
+    for line in kept {
+        writeln!(file, "{line}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::member::Member;
+    use crate::nn_architecture::NN_Architecture;
+    use crate::snakegame::GameOutcome;
+    use crate::config::TempPath;
+
+    fn member(fitness: f64, generation: usize, seed: u8) -> Member {
+        let mut m = Member::new(NN_Architecture::new(), Some([seed; 32]), generation);
+        m.fitness = fitness;
+        m.apples_eaten = 2;
+        m.steps_survived = 40;
+        m.outcomes.record(GameOutcome::Wall);
+        m.outcomes.record(GameOutcome::Reversal);
+        m
+    }
+
+    #[test]
+    fn test_fitness_summary() {
+        let members = vec![member(10.0, 0, 1), member(40.0, 2, 2), member(20.0, 4, 3), member(20.0, 4, 4)];
+        let stats = GenerationStats::from_members(4, &members, 0.5);
+
+        assert_eq!(stats.games, 8);
+        assert_eq!(stats.min_fitness, 10.0);
+        assert_eq!(stats.median_fitness, 20.0);
+        assert_eq!(stats.max_fitness, 40.0);
+        assert_eq!(stats.mean_fitness, 22.5);
+        assert!((stats.stddev_fitness - 118.75_f64.sqrt()).abs() < 1e-9);
+        assert_eq!(stats.distinct_fitness, 3);
+        assert_eq!(stats.outcomes.wall, 4);
+        assert_eq!(stats.outcomes.reversal, 4);
+        assert_eq!(stats.apples_per_game, 1.0);
+        assert_eq!(stats.steps_per_game, 20.0);
+        assert_eq!(stats.mean_age, 1.5);
+        assert!(stats.genome_spread > 0.0);
+    }
+
+    #[test]
+    fn test_identical_members_have_no_spread() {
+        let members = vec![member(5.0, 0, 9), member(5.0, 0, 9)];
+        let stats = GenerationStats::from_members(0, &members, 0.0);
+        assert_eq!(stats.genome_spread, 0.0);
+        assert_eq!(stats.distinct_fitness, 1);
+    }
+
+    #[test]
+    fn test_csv_row_matches_header() {
+        let stats = GenerationStats::from_members(1, &[member(1.0, 0, 1)], 0.0);
+        assert_eq!(stats.to_csv_row().split(',').count(), CSV_HEADER.split(',').count());
+    }
+
+    #[test]
+    fn test_reopening_drops_generations_to_redo() {
+        let temp = TempPath::new("stats");
+        let dir = temp.path();
+        std::fs::create_dir_all(dir).unwrap();
+        let members = vec![member(1.0, 0, 1)];
+
+        let mut log = StatsLog::open(dir, 0).unwrap();
+        for generation in 0..5 {
+            log.append(&GenerationStats::from_members(generation, &members, 0.0)).unwrap();
+        }
+        drop(log);
+        let mut log = StatsLog::open(dir, 3).unwrap();
+        log.append(&GenerationStats::from_members(3, &members, 0.0)).unwrap();
+        drop(log);
+
+        let jsonl = std::fs::read_to_string(dir.join(STATS_JSONL_FILE)).unwrap();
+        let csv = std::fs::read_to_string(dir.join(STATS_CSV_FILE)).unwrap();
+
+        let generations: Vec<usize> = jsonl
+            .lines()
+            .map(|l| serde_json::from_str::<GenerationStats>(l).unwrap().generation)
+            .collect();
+        assert_eq!(generations, vec![0, 1, 2, 3]);
+        assert_eq!(csv.lines().next(), Some(CSV_HEADER));
+        assert_eq!(csv.lines().count(), 5);
+    }
+}